@@ -0,0 +1,260 @@
+//! Derive macros for dsp-utils
+//!
+//! Use them through `dsp_utils`, which reexports them next to the traits they implement.
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::format_ident;
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::Data;
+use syn::DeriveInput;
+use syn::Fields;
+use syn::Ident;
+use syn::LitStr;
+use syn::Path;
+use syn::Type;
+
+/// Generate a `ParamsBlock` struct for a struct implementing `Params`
+///
+/// Place it on your `Params` struct. It generates a struct named `{YourParams}Block`, with a `Vec` for every `FloatParam`
+/// (`Vec<f32>`), `IntParam` (`Vec<i32>`) and `BoolParam` (`Vec<bool>`) field marked with `#[id = "..."]`, and implements
+/// `dsp_utils::ParamsBlock` for it. The fields of the block have the same names as the params.
+///
+/// Float and int params are filled from their smoothers, bool params are filled with their current value.
+///
+/// Attributes:
+///
+/// | Attribute                                 | Placement | Note                                                        |
+/// |-------------------------------------------|-----------|-------------------------------------------------------------|
+/// | `#[params_block(name = "Name")]`          | struct    | name of the generated struct                                |
+/// | `#[params_block(crate = "path")]`         | struct    | path to `dsp_utils`, e.g. `"plugin_utils::dsp_utils"`       |
+/// | `#[params_block(skip)]`                   | field     | do not generate a block for the param                       |
+/// | `#[params_block(unsmoothed)]`             | field     | fill the block with the current value, skipping the smoother |
+///
+/// # Examples
+///
+/// ```no_run
+/// #[derive(Params, ParamsBlock)]
+/// #[params_block(crate = "plugin_utils::dsp_utils")]
+/// struct PluginParams {
+///     #[id = "gain"]
+///     pub gain: FloatParam,
+///     #[id = "mode"]
+///     #[params_block(unsmoothed)]
+///     pub mode: IntParam,
+///     #[id = "ui-scale"]
+///     #[params_block(skip)]
+///     pub ui_scale: FloatParam,
+/// }
+///
+/// // Generates `PluginParamsBlock` with `pub gain: Vec<f32>` and `pub mode: Vec<i32>` fields
+/// struct PluginSingleChannelProcessor {}
+///
+/// impl SingleChannelProcessor for PluginSingleChannelProcessor {
+///     type ParamsBlock = PluginParamsBlock;
+///     [...]
+/// }
+/// ```
+#[proc_macro_derive(ParamsBlock, attributes(params_block))]
+pub fn derive_params_block(input: TokenStream) -> TokenStream {
+    let ast = syn::parse_macro_input!(input as DeriveInput);
+
+    match params_block(ast) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+// Kind of param, decided by the name of the field type
+enum ParamKind {
+    Float,
+    Int,
+    Bool,
+}
+
+struct BlockField {
+    ident: Ident,
+    kind: ParamKind,
+    unsmoothed: bool,
+}
+
+fn params_block(ast: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let params_ident = &ast.ident;
+    let vis = &ast.vis;
+
+    let mut block_ident = format_ident!("{}Block", params_ident);
+    let mut krate: Path = syn::parse_quote!(::dsp_utils);
+
+    for attr in ast
+        .attrs
+        .iter()
+        .filter(|a| a.path().is_ident("params_block"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                let name: LitStr = meta.value()?.parse()?;
+                block_ident = Ident::new(&name.value(), name.span());
+                Ok(())
+            } else if meta.path.is_ident("crate") {
+                let path: LitStr = meta.value()?.parse()?;
+                krate = path.parse()?;
+                Ok(())
+            } else {
+                Err(meta.error("expected `name` or `crate`"))
+            }
+        })?;
+    }
+
+    if !ast.generics.params.is_empty() {
+        return Err(syn::Error::new(
+            ast.generics.span(),
+            "ParamsBlock cannot be derived for generic structs",
+        ));
+    }
+
+    let fields = match &ast.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new(
+                    Span::call_site(),
+                    "ParamsBlock can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                "ParamsBlock can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut block_fields = Vec::new();
+    for field in fields {
+        // Only fields registered as params by `#[derive(Params)]`
+        if !field.attrs.iter().any(|a| a.path().is_ident("id")) {
+            continue;
+        }
+
+        let mut skip = false;
+        let mut unsmoothed = false;
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|a| a.path().is_ident("params_block"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("skip") {
+                    skip = true;
+                    Ok(())
+                } else if meta.path.is_ident("unsmoothed") {
+                    unsmoothed = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `skip` or `unsmoothed`"))
+                }
+            })?;
+        }
+        if skip {
+            continue;
+        }
+
+        let kind = match param_kind(&field.ty) {
+            Some(kind) => kind,
+            None if unsmoothed => {
+                return Err(syn::Error::new(
+                    field.ty.span(),
+                    "only FloatParam, IntParam and BoolParam fields are supported",
+                ))
+            }
+            None => continue,
+        };
+
+        block_fields.push(BlockField {
+            ident: field.ident.clone().expect("named field"),
+            kind,
+            unsmoothed,
+        });
+    }
+
+    let declarations = block_fields.iter().map(|field| {
+        let ident = &field.ident;
+        let ty = match field.kind {
+            ParamKind::Float => quote!(f32),
+            ParamKind::Int => quote!(i32),
+            ParamKind::Bool => quote!(bool),
+        };
+        quote!(pub #ident: ::std::vec::Vec<#ty>)
+    });
+
+    let allocations = block_fields.iter().map(|field| {
+        let ident = &field.ident;
+        let zero = match field.kind {
+            ParamKind::Float => quote!(0_f32),
+            ParamKind::Int => quote!(0_i32),
+            ParamKind::Bool => quote!(false),
+        };
+        quote!(#ident: ::std::vec![#zero; block_size])
+    });
+
+    let updates = block_fields.iter().map(|field| {
+        let ident = &field.ident;
+        match field.kind {
+            ParamKind::Float | ParamKind::Int if !field.unsmoothed => quote! {
+                self.params.#ident.smoothed.next_block(&mut self.#ident, block_len);
+            },
+            _ => quote! {
+                self.#ident[0..block_len].fill(self.params.#ident.value());
+            },
+        }
+    });
+
+    let doc = format!(
+        "Blocks of [`{}`] values, generated by `#[derive(ParamsBlock)]`",
+        params_ident
+    );
+
+    Ok(quote! {
+        #[doc = #doc]
+        #vis struct #block_ident {
+            params: ::std::sync::Arc<#params_ident>,
+            pub block_size: usize,
+            #(#declarations,)*
+        }
+
+        impl #krate::ParamsBlock for #block_ident {
+            type Params = #params_ident;
+
+            fn new(params: ::std::sync::Arc<Self::Params>, block_size: usize) -> Self {
+                Self {
+                    params,
+                    block_size,
+                    #(#allocations,)*
+                }
+            }
+
+            fn from_params(&mut self) {
+                #krate::ParamsBlock::partial_from_params(self, self.block_size);
+            }
+
+            fn partial_from_params(&mut self, block_len: usize) {
+                #(#updates)*
+            }
+        }
+    })
+}
+
+fn param_kind(ty: &Type) -> Option<ParamKind> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+
+    match path.path.segments.last()?.ident.to_string().as_str() {
+        "FloatParam" => Some(ParamKind::Float),
+        "IntParam" => Some(ParamKind::Int),
+        "BoolParam" => Some(ParamKind::Bool),
+        _ => None,
+    }
+}
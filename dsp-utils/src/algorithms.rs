@@ -1,7 +1,11 @@
+mod fft;
 pub mod filter;
 mod mdct;
 mod window;
 
+pub use fft::Complex;
+pub use fft::FFT;
+
 pub use mdct::DCT;
 pub use mdct::MDCT;
 
@@ -10,6 +14,10 @@ pub use window::SignalWindow;
 
 pub use filter::Filter;
 
+mod oversampling;
+pub use oversampling::FilterPhase;
+pub use oversampling::Oversampler;
+
 pub mod pitch_shift;
 
 pub trait Process {
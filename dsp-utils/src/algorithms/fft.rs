@@ -0,0 +1,340 @@
+use std::ops::{Add, Mul, Sub};
+
+use crate::Sample;
+
+/// Complex number, the bins of a [`FFT`]
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct Complex<T: Sample = f32> {
+    pub re: T,
+    pub im: T,
+}
+
+impl<T: Sample> Complex<T> {
+    pub fn new(re: T, im: T) -> Self {
+        Self { re, im }
+    }
+
+    /// Complex number with given magnitude and phase, in radians
+    pub fn from_polar(magnitude: T, phase: T) -> Self {
+        Self::new(magnitude * phase.cos(), magnitude * phase.sin())
+    }
+
+    /// Magnitude
+    pub fn norm(self) -> T {
+        (self.re * self.re + self.im * self.im).sqrt()
+    }
+
+    /// Phase in radians, in `-PI..=PI`
+    pub fn arg(self) -> T {
+        self.im.atan2(self.re)
+    }
+
+    /// Complex conjugate
+    pub fn conj(self) -> Self {
+        Self::new(self.re, -self.im)
+    }
+
+    // Multiplies by `i`
+    fn rotate(self) -> Self {
+        Self::new(-self.im, self.re)
+    }
+
+    fn scale(self, factor: T) -> Self {
+        Self::new(self.re * factor, self.im * factor)
+    }
+}
+
+impl<T: Sample> Add for Complex<T> {
+    type Output = Self;
+    fn add(self, other: Self) -> Self {
+        Self::new(self.re + other.re, self.im + other.im)
+    }
+}
+
+impl<T: Sample> Sub for Complex<T> {
+    type Output = Self;
+    fn sub(self, other: Self) -> Self {
+        Self::new(self.re - other.re, self.im - other.im)
+    }
+}
+
+impl<T: Sample> Mul for Complex<T> {
+    type Output = Self;
+    fn mul(self, other: Self) -> Self {
+        Self::new(
+            self.re * other.re - self.im * other.im,
+            self.re * other.im + self.im * other.re,
+        )
+    }
+}
+
+/// Real input fft processor
+///
+/// Transforms `size` real samples into the `size / 2 + 1` bins from DC up to Nyquist, and back. The real signal is packed
+/// into a complex fft of half the size, so it does half the work of a complex fft. All tables and buffers are allocated in
+/// [`Self::new`], so it can be used inside `process`. Works on any [`Sample`] type, `f32` by default.
+///
+/// # Examples
+///
+/// ```
+/// let mut fft = FFT::new(8);
+///
+/// let block = vec![0_f32, 1_f32, 0_f32, -1_f32, 0_f32, 1_f32, 0_f32, -1_f32];
+/// let mut bins = vec![Complex::default(); 5];
+/// fft.fft(&block, &mut bins);
+///
+/// // All the energy is in the bin of a quarter of the sample rate
+/// let mut magnitudes = vec![0_f32; 5];
+/// let mut phases = vec![0_f32; 5];
+/// fft.fft_polar(&block, &mut magnitudes, &mut phases);
+///
+/// let mut output = vec![0_f32; 8];
+/// fft.ifft(&bins, &mut output);
+/// ```
+pub struct FFT<T: Sample = f32> {
+    size: usize,
+    // `e^(-2 PI i k / size)` for `k` in `0..size / 2`
+    twiddles: Vec<Complex<T>>,
+    // Index of every element of the half size fft after bit reversal
+    reversed: Vec<usize>,
+    buffer: Vec<Complex<T>>,
+    bins: Vec<Complex<T>>,
+}
+
+impl<T: Sample> FFT<T> {
+    /// Initialize the processor with given `size`
+    ///
+    /// Panics if `size` is not a power of 2, or smaller than 2.
+    /// This function allocates memory, and should be used only in [`nih_plug::prelude::Plugin::initialize`] call
+    pub fn new(size: usize) -> Self {
+        assert!(size.is_power_of_two() && size >= 2);
+        let half = size / 2;
+        let bits = half.ilog2();
+        Self {
+            size,
+            twiddles: (0..half)
+                .map(|k| {
+                    let angle = -2_f64 * std::f64::consts::PI * k as f64 / size as f64;
+                    Complex::new(T::from_f64(angle.cos()), T::from_f64(angle.sin()))
+                })
+                .collect(),
+            reversed: (0..half)
+                .map(|i| match bits {
+                    0 => 0,
+                    _ => i.reverse_bits() >> (usize::BITS - bits),
+                })
+                .collect(),
+            buffer: vec![Complex::default(); half],
+            bins: vec![Complex::default(); half + 1],
+        }
+    }
+
+    /// Number of samples transformed
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Number of bins, `size / 2 + 1`
+    pub fn bins(&self) -> usize {
+        self.size / 2 + 1
+    }
+
+    /// Transforms `block` into `output` bins with forward fft
+    ///
+    /// `block` needs to be of length `size`
+    ///
+    /// `output` needs to be of length `size / 2 + 1`
+    ///
+    /// This will be asserted if built with `benchmark` feature
+    pub fn fft(&mut self, block: &[T], output: &mut [Complex<T>]) {
+        #[cfg(feature = "benchmark")]
+        {
+            assert_eq!(block.len(), self.size);
+            assert_eq!(output.len(), self.bins());
+        }
+
+        let half = self.size / 2;
+        for (value, pair) in self.buffer.iter_mut().zip(block.chunks_exact(2)) {
+            *value = Complex::new(pair[0], pair[1]);
+        }
+        self.transform(false);
+
+        // Split the spectra of the even and odd samples, and combine them into the full spectrum
+        let one_half = T::from_f32(0.5_f32);
+        for (k, bin) in output.iter_mut().enumerate().take(half + 1) {
+            let z = self.buffer[k % half];
+            let mirrored = self.buffer[(half - k) % half].conj();
+            let even = (z + mirrored).scale(one_half);
+            let odd = (mirrored - z).rotate().scale(one_half);
+            *bin = even + self.twiddle(k) * odd;
+        }
+    }
+
+    /// Transforms `bins` into `output` block with inverse fft
+    ///
+    /// The output is scaled by `1 / size`, so it returns the block passed to [`Self::fft`]. The imaginary parts of the DC
+    /// and Nyquist bins are ignored.
+    ///
+    /// `bins` needs to be of length `size / 2 + 1`
+    ///
+    /// `output` needs to be of length `size`
+    ///
+    /// This will be asserted if built with `benchmark` feature
+    pub fn ifft(&mut self, bins: &[Complex<T>], output: &mut [T]) {
+        #[cfg(feature = "benchmark")]
+        {
+            assert_eq!(bins.len(), self.bins());
+            assert_eq!(output.len(), self.size);
+        }
+
+        let half = self.size / 2;
+        let one_half = T::from_f32(0.5_f32);
+        for k in 0..half {
+            let mirrored = bins[half - k].conj();
+            let even = (bins[k] + mirrored).scale(one_half);
+            let odd = (bins[k] - mirrored).scale(one_half) * self.twiddle(k).conj();
+            self.buffer[k] = even + odd.rotate();
+        }
+        self.transform(true);
+
+        let scale = T::ONE / T::from_f64(half as f64);
+        for (value, pair) in self.buffer.iter().zip(output.chunks_exact_mut(2)) {
+            pair[0] = value.re * scale;
+            pair[1] = value.im * scale;
+        }
+    }
+
+    /// Transforms `block` with forward fft, into the magnitude and phase of every bin
+    ///
+    /// `magnitudes` and `phases` need to be of length `size / 2 + 1`, see [`Self::fft`]
+    pub fn fft_polar(&mut self, block: &[T], magnitudes: &mut [T], phases: &mut [T]) {
+        let mut bins = std::mem::take(&mut self.bins);
+        self.fft(block, &mut bins);
+        for ((bin, magnitude), phase) in bins.iter().zip(magnitudes).zip(phases) {
+            *magnitude = bin.norm();
+            *phase = bin.arg();
+        }
+        self.bins = bins;
+    }
+
+    /// Transforms the magnitude and phase of every bin into `output` block with inverse fft
+    ///
+    /// `magnitudes` and `phases` need to be of length `size / 2 + 1`, see [`Self::ifft`]
+    pub fn ifft_polar(&mut self, magnitudes: &[T], phases: &[T], output: &mut [T]) {
+        let mut bins = std::mem::take(&mut self.bins);
+        for ((bin, &magnitude), &phase) in bins.iter_mut().zip(magnitudes).zip(phases) {
+            *bin = Complex::from_polar(magnitude, phase);
+        }
+        self.ifft(&bins, output);
+        self.bins = bins;
+    }
+
+    // `e^(-2 PI i k / size)`, for `k` in `0..=size / 2`
+    fn twiddle(&self, k: usize) -> Complex<T> {
+        match self.twiddles.get(k) {
+            Some(&twiddle) => twiddle,
+            None => Complex::new(-T::ONE, T::ZERO),
+        }
+    }
+
+    // In place radix 2 complex fft of the buffer, unscaled
+    fn transform(&mut self, inverse: bool) {
+        let half = self.buffer.len();
+        for i in 0..half {
+            let j = self.reversed[i];
+            if i < j {
+                self.buffer.swap(i, j);
+            }
+        }
+
+        let mut len = 2;
+        while len <= half {
+            // Twiddles of the half size fft are every other one of the full size
+            let stride = self.size / len;
+            for start in (0..half).step_by(len) {
+                for j in 0..len / 2 {
+                    let twiddle = match inverse {
+                        false => self.twiddles[j * stride],
+                        true => self.twiddles[j * stride].conj(),
+                    };
+                    let a = self.buffer[start + j];
+                    let b = self.buffer[start + j + len / 2] * twiddle;
+                    self.buffer[start + j] = a + b;
+                    self.buffer[start + j + len / 2] = a - b;
+                }
+            }
+            len *= 2;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Complex, FFT};
+
+    fn get_test_case(size: usize) -> Vec<f64> {
+        (0..size)
+            .map(|i| ((i * 7919) % 23) as f64 - 11_f64 + (i as f64 * 0.3_f64).sin())
+            .collect()
+    }
+
+    fn dft(block: &[f64]) -> Vec<Complex<f64>> {
+        let size = block.len();
+        (0..=size / 2)
+            .map(|k| {
+                block
+                    .iter()
+                    .enumerate()
+                    .map(|(n, &x)| {
+                        let angle = -2_f64 * std::f64::consts::PI * (k * n) as f64 / size as f64;
+                        Complex::new(x * angle.cos(), x * angle.sin())
+                    })
+                    .fold(Complex::default(), |sum, value| sum + value)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_fft_dft() {
+        for size in [2, 4, 8, 64, 512] {
+            let block = get_test_case(size);
+            let expected = dft(&block);
+
+            let mut fft: FFT<f64> = FFT::new(size);
+            let mut bins = vec![Complex::default(); fft.bins()];
+            fft.fft(&block, &mut bins);
+            for (bin, expected) in bins.iter().zip(&expected) {
+                assert!(
+                    (*bin - *expected).norm() < 1e-9_f64,
+                    "{size}: {bin:?} {expected:?}"
+                );
+            }
+
+            let mut output = vec![0_f64; size];
+            fft.ifft(&bins, &mut output);
+            for (sample, expected) in output.iter().zip(&block) {
+                assert!((sample - expected).abs() < 1e-9_f64);
+            }
+        }
+    }
+
+    #[test]
+    fn test_fft_polar() {
+        let block: Vec<f32> = get_test_case(32).iter().map(|&x| x as f32).collect();
+        let expected = dft(&get_test_case(32));
+
+        let mut fft = FFT::new(32);
+        let mut magnitudes = vec![0_f32; 17];
+        let mut phases = vec![0_f32; 17];
+        fft.fft_polar(&block, &mut magnitudes, &mut phases);
+        for (magnitude, expected) in magnitudes.iter().zip(&expected) {
+            assert!((*magnitude as f64 - expected.norm()).abs() < 1e-3_f64);
+        }
+
+        let mut output = vec![0_f32; 32];
+        fft.ifft_polar(&magnitudes, &phases, &mut output);
+        for (sample, expected) in output.iter().zip(&block) {
+            assert!((sample - expected).abs() < 1e-4_f32);
+        }
+    }
+}
@@ -2,14 +2,18 @@ mod chamberlin_approx;
 
 pub use chamberlin_approx::ChamberlinApprox;
 
+use crate::Sample;
+
 /// Filter taking single sample inputs
-pub trait Filter {
+///
+/// `T` is the [`Sample`] type the filter runs in, `f32` by default
+pub trait Filter<T: Sample = f32> {
     /// Initialize filter
     fn new(sample_rate: f32, f_type: FilterType) -> Self;
     /// Change filter type, should return `true` if the type is supported, `false` otherwise
     fn set_type(&mut self, f_type: FilterType) -> bool;
     /// Process sample and return the result
-    fn process(&mut self, sample: f32, cutoff_frequency: f32, q: f32) -> f32;
+    fn process(&mut self, sample: T, cutoff_frequency: T, q: T) -> T;
     /// Initialize/reinitialize constants
     fn init(&mut self);
 }
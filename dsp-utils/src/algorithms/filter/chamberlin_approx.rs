@@ -1,42 +1,41 @@
 // Algorithm source: https://www.musicdsp.org/en/latest/Filters/23-state-variable.html
 
-use std::f32::consts::PI;
-
 use crate::algorithms::Filter;
+use crate::Sample;
 
 use super::FilterType;
 
 /// Filter `musicdsp.org/en/latest/Filters/23-state-variable.html` Supports
 /// `LowPass`, `HighPass`, `BandPass` and `Notch` configurations
-pub struct ChamberlinApprox {
-    fs: f32,
-    freq: f32,
-    f: f32,
-    q: f32,
+pub struct ChamberlinApprox<T: Sample = f32> {
+    fs: T,
+    freq: T,
+    f: T,
+    q: T,
 
-    low: f32,
-    high: f32,
-    band: f32,
-    notch: f32,
+    low: T,
+    high: T,
+    band: T,
+    notch: T,
 
     f_type: FilterType,
 }
 
-impl Filter for ChamberlinApprox {
+impl<T: Sample> Filter<T> for ChamberlinApprox<T> {
     fn init(&mut self) {
-        self.f = 2_f32 * (PI * self.freq / self.fs).sin();
+        self.f = T::from_f32(2_f32) * (T::PI * self.freq / self.fs).sin();
     }
 
     fn new(sample_rate: f32, f_type: FilterType) -> Self {
         Self {
-            fs: sample_rate,
-            freq: -1_f32,
-            f: 0_f32,
-            q: 1_f32,
-            low: 0_f32,
-            high: 0_f32,
-            band: 0_f32,
-            notch: 0_f32,
+            fs: T::from_f32(sample_rate),
+            freq: -T::ONE,
+            f: T::ZERO,
+            q: T::ONE,
+            low: T::ZERO,
+            high: T::ZERO,
+            band: T::ZERO,
+            notch: T::ZERO,
             f_type,
         }
     }
@@ -53,7 +52,7 @@ impl Filter for ChamberlinApprox {
         }
     }
 
-    fn process(&mut self, sample: f32, cutoff_frequency: f32, q: f32) -> f32 {
+    fn process(&mut self, sample: T, cutoff_frequency: T, q: T) -> T {
         if cutoff_frequency != self.freq {
             self.freq = cutoff_frequency;
             self.init();
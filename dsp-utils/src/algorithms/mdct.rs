@@ -1,12 +1,15 @@
 mod dct;
 pub use dct::DCT;
 
+use crate::Sample;
+
 /// Mdct processor
 ///
 /// Buffers some data to allow for smooth encoding and decoding. Forward mdct takes in a slice of length `block_size` and outputs
 /// its data into a slice of length `block_size * 2`. Inverse mdct does the opposite, and decodes the blocks.
 ///
-/// Can be easily used in [`crate::SingleChannelProcessor`], by setting it as its field.
+/// Can be easily used in [`crate::SingleChannelProcessor`], by setting it as its field. Works on any [`Sample`] type, `f32` by
+/// default.
 ///
 /// # Examples
 ///
@@ -29,17 +32,17 @@ pub use dct::DCT;
 /// mdct.imdct(&mut output, &mut imdct_block);
 /// println!("{:?}", imdct_block);
 /// ```
-pub struct MDCT {
-    dct: dct::DCT,
+pub struct MDCT<T: Sample = f32> {
+    dct: dct::DCT<T>,
     block_size: usize,
 
-    dct_buffer: Vec<f32>,
-    idct_buffer: Vec<f32>,
-    window: Vec<f32>,
-    temp_buffer: Vec<f32>,
+    dct_buffer: Vec<T>,
+    idct_buffer: Vec<T>,
+    window: Vec<T>,
+    temp_buffer: Vec<T>,
 }
 
-impl MDCT {
+impl<T: Sample> MDCT<T> {
     /// Initialize the processor with given `block_size`
     ///
     /// Panics if `block_size` is not a power of 2.
@@ -50,17 +53,19 @@ impl MDCT {
             dct: dct::DCT::new(block_size * 2),
             block_size,
 
-            dct_buffer: vec![0_f32; block_size * 2],
-            idct_buffer: vec![0_f32; block_size],
+            dct_buffer: vec![T::ZERO; block_size * 2],
+            idct_buffer: vec![T::ZERO; block_size],
             window: (0..block_size * 2)
                 .into_iter()
                 .map(|i| {
-                    (std::f32::consts::PI * i as f32 / ((2 * block_size + 1) as f32))
-                        .sin()
-                        .powi(2)
+                    T::from_f64(
+                        (std::f64::consts::PI * i as f64 / ((2 * block_size + 1) as f64))
+                            .sin()
+                            .powi(2),
+                    )
                 })
                 .collect(),
-            temp_buffer: vec![0_f32; block_size * 2],
+            temp_buffer: vec![T::ZERO; block_size * 2],
         }
     }
 
@@ -71,7 +76,7 @@ impl MDCT {
     /// `output_block` needs to be of length `block_size * 2`
     ///
     /// This will be asserted if built with `benchmark` feature
-    pub fn mdct(&mut self, block: &[f32], output_block: &mut [f32]) {
+    pub fn mdct(&mut self, block: &[T], output_block: &mut [T]) {
         #[cfg(feature = "benchmark")]
         {
             assert_eq!(block.len(), self.block_size);
@@ -96,7 +101,7 @@ impl MDCT {
     /// `output_block` needs to be of length `block_size`
     ///
     /// This will be asserted if built with `benchmark` feature
-    pub fn imdct(&mut self, dct_block: &mut [f32], output_block: &mut [f32]) {
+    pub fn imdct(&mut self, dct_block: &mut [T], output_block: &mut [T]) {
         #[cfg(feature = "benchmark")]
         {
             assert_eq!(output_block.len(), self.block_size);
@@ -1,47 +1,51 @@
-pub struct DCT {
-    constants: Vec<Vec<f32>>,
-    multiplier: f32,
+use crate::Sample;
+
+pub struct DCT<T: Sample = f32> {
+    constants: Vec<Vec<T>>,
+    multiplier: T,
 }
 
-impl DCT {
+impl<T: Sample> DCT<T> {
     pub fn new(block_size: usize) -> Self {
         let mut size: usize = block_size;
-        let mut constants: Vec<Vec<f32>> = Vec::new();
+        let mut constants: Vec<Vec<T>> = Vec::new();
         while size >= 2 {
             size /= 2;
-            let mut temp: Vec<f32> = vec![0_f32; size];
+            let mut temp: Vec<T> = vec![T::ZERO; size];
             for i in 0..size {
-                temp[i] = 0.5_f32
-                    / (((i as f32) + 0.5_f32) * std::f32::consts::PI / ((size as f32) * 2_f32))
-                        .cos();
+                temp[i] = T::from_f64(
+                    0.5_f64
+                        / (((i as f64) + 0.5_f64) * std::f64::consts::PI / ((size as f64) * 2_f64))
+                            .cos(),
+                );
             }
             constants.push(temp);
         }
         Self {
             constants,
-            multiplier: 0.5_f32.powi(block_size.ilog2() as i32 - 1),
+            multiplier: T::from_f64(0.5_f64.powi(block_size.ilog2() as i32 - 1)),
         }
     }
 
-    pub fn dct(&self, data: &mut [f32], temp: &mut [f32]) {
+    pub fn dct(&self, data: &mut [T], temp: &mut [T]) {
         self.forward_dct(data, temp, 0);
-        data[0] = data[0] * 0.5_f32;
+        data[0] = data[0] * T::from_f32(0.5_f32);
         for e in data {
             *e = *e * self.multiplier;
         }
     }
 
-    pub fn idct(&self, data: &mut [f32], temp: &mut [f32]) {
+    pub fn idct(&self, data: &mut [T], temp: &mut [T]) {
         self.inverse_dct(data, temp, 0);
     }
 
-    fn forward_dct(&self, data: &mut [f32], temp: &mut [f32], depth: usize) {
+    fn forward_dct(&self, data: &mut [T], temp: &mut [T], depth: usize) {
         let len: usize = data.len();
         let half_len: usize = len / 2;
 
         for i in 0..half_len {
-            let x: f32 = data[i];
-            let y: f32 = data[len - i - 1];
+            let x: T = data[i];
+            let y: T = data[len - i - 1];
             temp[i] = x + y;
             temp[i + half_len] = (x - y) * self.constants[depth][i];
         }
@@ -59,7 +63,7 @@ impl DCT {
         data[len - 1] = temp[len - 1];
     }
 
-    fn inverse_dct(&self, data: &mut [f32], temp: &mut [f32], depth: usize) {
+    fn inverse_dct(&self, data: &mut [T], temp: &mut [T], depth: usize) {
         let len: usize = data.len();
         let half_len: usize = len / 2;
 
@@ -117,4 +121,19 @@ mod test_dct {
             assert!((data[i] - data_orig[i]) < 0.01_f32);
         }
     }
+
+    #[test]
+    fn test_dct_idct_f64() {
+        let data_orig: Vec<f64> = get_test_case().iter().map(|&x| x as f64).collect();
+        let mut data = data_orig.clone();
+        let mut temp = [0_f64; 8];
+
+        let dct: DCT<f64> = DCT::new(8);
+
+        dct.dct(data.as_mut_slice(), temp.as_mut_slice());
+        dct.idct(data.as_mut_slice(), temp.as_mut_slice());
+        for i in 0..8 {
+            assert!((data[i] - data_orig[i]).abs() < 1e-9_f64);
+        }
+    }
 }
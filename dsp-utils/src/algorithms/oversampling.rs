@@ -0,0 +1,514 @@
+use std::f64::consts::PI;
+
+use crate::Sample;
+
+/// Phase response of the [`Oversampler`] filters
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FilterPhase {
+    /// Symmetric filters. The phase is not altered at all, at the cost of more latency
+    Linear,
+    /// Filters with the same magnitude response as [`FilterPhase::Linear`], but most of the delay removed. Keeps transients
+    /// sharp, but shifts the phase of the high frequencies
+    Minimum,
+}
+
+/// Polyphase half-band oversampler
+///
+/// Upsamples blocks by `factor`, which can be `1`, `2`, `4`, `8` or `16`, and downsamples them back after processing. Each
+/// doubling of the sample rate is a separate stage, filtering out the images and aliases with a half-band lowpass filter.
+/// The first stage is the steepest, the following ones only need to remove the images of the original band, so they use
+/// fewer taps.
+///
+/// The round trip through [`Self::upsample`] and [`Self::downsample`] delays the signal by [`Self::latency`] samples. Works
+/// on any [`Sample`] type, `f32` by default. Clone it to get more oversamplers with the same filters, without designing
+/// them again.
+///
+/// # Examples
+///
+/// ```
+/// let mut oversampler = Oversampler::new(4, FilterPhase::Linear, 64);
+///
+/// let block = vec![0_f32; 64];
+/// let mut upsampled = vec![0_f32; 256];
+/// oversampler.upsample(&block, &mut upsampled);
+///
+/// // Process `upsampled` at 4 times the sample rate here
+///
+/// let mut output = vec![0_f32; 64];
+/// oversampler.downsample(&upsampled, &mut output);
+/// ```
+#[derive(Clone)]
+pub struct Oversampler<T: Sample = f32> {
+    factor: usize,
+    stages: Vec<Stage<T>>,
+    // Output of every upsampling stage but the last one, reused by the downsampling stages
+    buffers: Vec<Vec<T>>,
+
+    // Delays the upsampled signal, so the round trip latency is a whole number of samples
+    padding: Vec<T>,
+    padding_pos: usize,
+    latency: usize,
+}
+
+// Half of the taps, without the center one, of each stage filter
+const STAGE_ORDERS: [usize; 4] = [15, 5, 3, 3];
+
+// Kaiser window parameter, gives around 90 dB of stopband attenuation
+const KAISER_BETA: f64 = 9_f64;
+
+impl<T: Sample> Oversampler<T> {
+    /// Initialize the oversampler, for blocks of up to `max_block_size` samples
+    ///
+    /// # Panics
+    ///
+    /// Panics if `factor` is not a power of two between `1` and `16`
+    pub fn new(factor: usize, phase: FilterPhase, max_block_size: usize) -> Self {
+        assert!(
+            factor.is_power_of_two() && factor <= 16,
+            "Oversampling factor must be 1, 2, 4, 8 or 16"
+        );
+        let stage_count = factor.trailing_zeros() as usize;
+
+        let stages: Vec<Stage<T>> = STAGE_ORDERS[0..stage_count]
+            .iter()
+            .map(|&order| {
+                let taps = half_band(order);
+                match phase {
+                    FilterPhase::Linear => Stage::new(&taps),
+                    FilterPhase::Minimum => Stage::new(&minimum_phase(&taps)),
+                }
+            })
+            .collect();
+
+        // Every stage delays the signal by its group delay in samples at its own input rate, sum them up at the highest rate
+        let delay = stages
+            .iter()
+            .enumerate()
+            .map(|(stage, filter)| filter.delay * (factor >> stage) as f64)
+            .sum::<f64>()
+            .round() as usize;
+        let padding = (factor - delay % factor) % factor;
+
+        Self {
+            factor,
+            buffers: (1..stage_count)
+                .map(|stage| vec![T::ZERO; max_block_size << stage])
+                .collect(),
+            stages,
+            padding: vec![T::ZERO; padding],
+            padding_pos: 0,
+            latency: (delay + padding) / factor,
+        }
+    }
+
+    /// The oversampling factor
+    pub fn factor(&self) -> usize {
+        self.factor
+    }
+
+    /// Delay of the round trip through [`Self::upsample`] and [`Self::downsample`], in samples at the original rate
+    ///
+    /// Exact for [`FilterPhase::Linear`]. For [`FilterPhase::Minimum`] it is the delay of the low frequencies, rounded
+    pub fn latency(&self) -> usize {
+        self.latency
+    }
+
+    /// Upsample the `input` into `output`, which has to be `factor` times longer
+    pub fn upsample(&mut self, input: &[T], output: &mut [T]) {
+        debug_assert_eq!(input.len() * self.factor, output.len());
+
+        let Some((last, stages)) = self.stages.split_last_mut() else {
+            output.copy_from_slice(input);
+            return;
+        };
+
+        let mut len = input.len();
+        for (stage, filter) in stages.iter_mut().enumerate() {
+            let (previous, next) = self.buffers.split_at_mut(stage);
+            let source = match previous.last() {
+                Some(buffer) => &buffer[0..len],
+                None => input,
+            };
+            filter.upsample(source, &mut next[0][0..len * 2]);
+            len *= 2;
+        }
+        let source = match self.buffers.last() {
+            Some(buffer) => &buffer[0..len],
+            None => input,
+        };
+        last.upsample(source, output);
+
+        if !self.padding.is_empty() {
+            for sample in output.iter_mut() {
+                std::mem::swap(sample, &mut self.padding[self.padding_pos]);
+                self.padding_pos = (self.padding_pos + 1) % self.padding.len();
+            }
+        }
+    }
+
+    /// Downsample the `input` into `output`, which has to be `factor` times shorter
+    pub fn downsample(&mut self, input: &[T], output: &mut [T]) {
+        debug_assert_eq!(output.len() * self.factor, input.len());
+
+        let Some((first, stages)) = self.stages.split_first_mut() else {
+            output.copy_from_slice(input);
+            return;
+        };
+
+        let mut len = input.len();
+        for (stage, filter) in stages.iter_mut().enumerate().rev() {
+            let (next, previous) = self.buffers.split_at_mut(stage + 1);
+            let source = match previous.first() {
+                Some(buffer) => &buffer[0..len],
+                None => input,
+            };
+            filter.downsample(source, &mut next[stage][0..len / 2]);
+            len /= 2;
+        }
+        let source = match self.buffers.first() {
+            Some(buffer) => &buffer[0..len],
+            None => input,
+        };
+        first.downsample(source, output);
+    }
+
+    /// Clear the state of all filters
+    pub fn reset(&mut self) {
+        for stage in self.stages.iter_mut() {
+            stage.reset();
+        }
+        self.padding.fill(T::ZERO);
+        self.padding_pos = 0;
+    }
+}
+
+// Single 2x stage, running the filter `h` split into its even and odd taps
+//
+// Upsampling computes `y[m] = 2 * sum(h[k] * u[m - k])`, where `u` is the input with zeros inserted between the samples.
+// Downsampling computes `z[n] = sum(h[k] * v[2n - k])`. Taps are stored as pairs of (delay, coefficient), skipping the zero
+// taps of the half-band filters.
+#[derive(Clone)]
+struct Stage<T: Sample> {
+    up: [Vec<(usize, T)>; 2],
+    down: [Vec<(usize, T)>; 2],
+    input_history: History<T>,
+    even_history: History<T>,
+    odd_history: History<T>,
+    // Group delay of the filter at the higher rate
+    delay: f64,
+}
+
+impl<T: Sample> Stage<T> {
+    fn new(taps: &[f64]) -> Self {
+        let nonzero = || taps.iter().enumerate().filter(|(_, &tap)| tap != 0_f64);
+        let length = taps.len() / 2 + 1;
+
+        Self {
+            up: [
+                nonzero()
+                    .filter(|(k, _)| k % 2 == 0)
+                    .map(|(k, &tap)| (k / 2, T::from_f64(2_f64 * tap)))
+                    .collect(),
+                nonzero()
+                    .filter(|(k, _)| k % 2 == 1)
+                    .map(|(k, &tap)| (k / 2, T::from_f64(2_f64 * tap)))
+                    .collect(),
+            ],
+            down: [
+                nonzero()
+                    .filter(|(k, _)| k % 2 == 0)
+                    .map(|(k, &tap)| (k / 2, T::from_f64(tap)))
+                    .collect(),
+                nonzero()
+                    .filter(|(k, _)| k % 2 == 1)
+                    .map(|(k, &tap)| (k / 2, T::from_f64(tap)))
+                    .collect(),
+            ],
+            input_history: History::new(length),
+            even_history: History::new(length),
+            odd_history: History::new(length),
+            delay: taps
+                .iter()
+                .enumerate()
+                .map(|(k, tap)| k as f64 * tap)
+                .sum::<f64>()
+                / taps.iter().sum::<f64>(),
+        }
+    }
+
+    fn upsample(&mut self, input: &[T], output: &mut [T]) {
+        for (&sample, pair) in input.iter().zip(output.chunks_exact_mut(2)) {
+            self.input_history.push(sample);
+            pair[0] = self.input_history.dot(&self.up[0]);
+            pair[1] = self.input_history.dot(&self.up[1]);
+        }
+    }
+
+    fn downsample(&mut self, input: &[T], output: &mut [T]) {
+        for (pair, sample) in input.chunks_exact(2).zip(output.iter_mut()) {
+            self.even_history.push(pair[0]);
+            // The odd history ends one sample before the current even one, so `v[2n - k]` for odd `k` has delay `k / 2`
+            *sample = self.even_history.dot(&self.down[0]) + self.odd_history.dot(&self.down[1]);
+            self.odd_history.push(pair[1]);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.input_history.reset();
+        self.even_history.reset();
+        self.odd_history.reset();
+    }
+}
+
+// Delay line, holding every sample twice so the last `len` samples are always contiguous
+#[derive(Clone)]
+struct History<T: Sample> {
+    data: Vec<T>,
+    pos: usize,
+    len: usize,
+}
+
+impl<T: Sample> History<T> {
+    fn new(len: usize) -> Self {
+        Self {
+            data: vec![T::ZERO; len * 2],
+            pos: 0,
+            len,
+        }
+    }
+
+    fn push(&mut self, sample: T) {
+        self.pos = if self.pos == 0 {
+            self.len - 1
+        } else {
+            self.pos - 1
+        };
+        self.data[self.pos] = sample;
+        self.data[self.pos + self.len] = sample;
+    }
+
+    // Sum of the samples delayed by the tap delays, multiplied by the tap coefficients
+    fn dot(&self, taps: &[(usize, T)]) -> T {
+        let window = &self.data[self.pos..self.pos + self.len];
+        taps.iter()
+            .map(|&(delay, coefficient)| window[delay] * coefficient)
+            .sum()
+    }
+
+    fn reset(&mut self) {
+        self.data.fill(T::ZERO);
+        self.pos = 0;
+    }
+}
+
+// Kaiser windowed half-band lowpass with `4 * order + 3` taps. Every other tap, apart from the center one, is exactly zero
+fn half_band(order: usize) -> Vec<f64> {
+    let len = 4 * order + 3;
+    let center = (len / 2) as isize;
+
+    let mut taps: Vec<f64> = (0..len as isize)
+        .map(|k| {
+            let offset = k - center;
+            if offset == 0 {
+                0.5_f64
+            } else if offset % 2 == 0 {
+                0_f64
+            } else {
+                let x = PI * offset as f64 / 2_f64;
+                let window = 1_f64 - (offset as f64 / center as f64).powi(2);
+                0.5_f64 * x.sin() / x * bessel_i0(KAISER_BETA * window.sqrt())
+                    / bessel_i0(KAISER_BETA)
+            }
+        })
+        .collect();
+
+    // The nonzero taps besides the center have to sum up to 0.5, for unity gain of both polyphase branches
+    let sum: f64 = taps.iter().sum::<f64>() - 0.5_f64;
+    for (k, tap) in taps.iter_mut().enumerate() {
+        if k as isize != center {
+            *tap *= 0.5_f64 / sum;
+        }
+    }
+    taps
+}
+
+// Zeroth order modified Bessel function of the first kind
+fn bessel_i0(x: f64) -> f64 {
+    let mut sum = 1_f64;
+    let mut term = 1_f64;
+    let mut k = 1_f64;
+    while term > sum * 1e-12_f64 {
+        term *= (x / (2_f64 * k)).powi(2);
+        sum += term;
+        k += 1_f64;
+    }
+    sum
+}
+
+// Number of frequency bins used to design minimum phase filters
+const MINIMUM_PHASE_BINS: usize = 1024;
+
+// Minimum phase filter with the same magnitude response as `taps`, using the real cepstrum
+fn minimum_phase(taps: &[f64]) -> Vec<f64> {
+    let n = MINIMUM_PHASE_BINS;
+    let twiddles: Vec<(f64, f64)> = (0..n)
+        .map(|i| {
+            let angle = 2_f64 * PI * i as f64 / n as f64;
+            (angle.cos(), angle.sin())
+        })
+        .collect();
+
+    // Log magnitude, with a floor for the zeros of the stopband
+    let spectrum = dft(&twiddles, taps.iter().map(|&tap| (tap, 0_f64)), n, false);
+    let log_magnitude: Vec<(f64, f64)> = spectrum
+        .iter()
+        .map(|&(re, im)| ((re * re + im * im).sqrt().max(1e-9_f64).ln(), 0_f64))
+        .collect();
+
+    // Fold the cepstrum onto the positive quefrencies
+    let cepstrum = dft(&twiddles, log_magnitude.into_iter(), n, true);
+    let folded = cepstrum.iter().enumerate().map(|(i, &(re, _))| match i {
+        0 => (re, 0_f64),
+        i if i < n / 2 => (2_f64 * re, 0_f64),
+        i if i == n / 2 => (re, 0_f64),
+        _ => (0_f64, 0_f64),
+    });
+
+    let spectrum = dft(&twiddles, folded, n, false)
+        .into_iter()
+        .map(|(re, im)| (re.exp() * im.cos(), re.exp() * im.sin()));
+    dft(&twiddles, spectrum, taps.len(), true)
+        .into_iter()
+        .map(|(re, _)| re)
+        .collect()
+}
+
+// First `outputs` bins of the discrete Fourier transform of size `twiddles.len()`, only used for filter design
+fn dft(
+    twiddles: &[(f64, f64)],
+    input: impl Iterator<Item = (f64, f64)>,
+    outputs: usize,
+    inverse: bool,
+) -> Vec<(f64, f64)> {
+    let n = twiddles.len();
+    let input: Vec<(f64, f64)> = input.collect();
+    let scale = if inverse { 1_f64 / n as f64 } else { 1_f64 };
+
+    (0..outputs)
+        .map(|k| {
+            let (mut re, mut im) = (0_f64, 0_f64);
+            for (j, &(x_re, x_im)) in input.iter().enumerate() {
+                let (cos, sin) = twiddles[(j * k) % n];
+                let sin = if inverse { sin } else { -sin };
+                re += x_re * cos - x_im * sin;
+                im += x_re * sin + x_im * cos;
+            }
+            (re * scale, im * scale)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::{half_band, minimum_phase, FilterPhase, Oversampler, STAGE_ORDERS};
+
+    // Magnitude of the frequency response at `omega` radians per sample
+    fn magnitude(taps: &[f64], omega: f64) -> f64 {
+        let (re, im) = taps
+            .iter()
+            .enumerate()
+            .fold((0_f64, 0_f64), |(re, im), (k, tap)| {
+                (
+                    re + tap * (omega * k as f64).cos(),
+                    im - tap * (omega * k as f64).sin(),
+                )
+            });
+        (re * re + im * im).sqrt()
+    }
+
+    // Makes sure the first stage filters pass the band below 0.4 pi, and reject everything above 0.6 pi
+
+    #[test]
+    fn test_filter_response() {
+        let linear = half_band(STAGE_ORDERS[0]);
+        let minimum = minimum_phase(&linear);
+
+        for taps in [linear, minimum] {
+            for i in 0..=100 {
+                let omega = PI * i as f64 / 100_f64;
+                let magnitude = magnitude(&taps, omega);
+                if omega <= 0.4_f64 * PI {
+                    assert!((magnitude - 1_f64).abs() < 1e-3_f64, "{omega}: {magnitude}");
+                } else if omega >= 0.6_f64 * PI {
+                    assert!(magnitude < 1e-4_f64, "{omega}: {magnitude}");
+                }
+            }
+        }
+    }
+
+    fn sine(len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|i| (2_f32 * std::f32::consts::PI * 0.05_f32 * i as f32).sin())
+            .collect()
+    }
+
+    // Runs the signal through the oversampler in blocks of `block_size`
+    fn round_trip(oversampler: &mut Oversampler, input: &[f32], block_size: usize) -> Vec<f32> {
+        let factor = oversampler.factor();
+        let mut upsampled = vec![0_f32; block_size * factor];
+        let mut output = vec![0_f32; input.len()];
+        for (block, output) in input.chunks(block_size).zip(output.chunks_mut(block_size)) {
+            oversampler.upsample(block, &mut upsampled[0..block.len() * factor]);
+            oversampler.downsample(&upsampled[0..block.len() * factor], output);
+        }
+        output
+    }
+
+    // Makes sure the round trip only delays the signal by the reported latency
+
+    #[test]
+    fn test_linear_phase_latency() {
+        let input = sine(1000);
+        for factor in [1, 2, 4, 8, 16] {
+            let mut oversampler: Oversampler = Oversampler::new(factor, FilterPhase::Linear, 64);
+            let output = round_trip(&mut oversampler, &input, 64);
+
+            let latency = oversampler.latency();
+            for i in 200..1000 {
+                assert!(
+                    (output[i] - input[i - latency]).abs() < 1e-3_f32,
+                    "{factor}x at {i}: {} != {}",
+                    output[i],
+                    input[i - latency]
+                );
+            }
+        }
+    }
+
+    // Makes sure the minimum phase filters have less latency, and keep the level of the signal
+
+    #[test]
+    fn test_minimum_phase() {
+        let input = sine(1000);
+        for factor in [2, 4, 8, 16] {
+            let mut linear: Oversampler = Oversampler::new(factor, FilterPhase::Linear, 64);
+            let mut oversampler: Oversampler = Oversampler::new(factor, FilterPhase::Minimum, 64);
+            assert!(oversampler.latency() < linear.latency());
+
+            let output = round_trip(&mut oversampler, &input, 64);
+            let peak = output[200..1000]
+                .iter()
+                .fold(0_f32, |peak, sample| peak.max(sample.abs()));
+            assert!((peak - 1_f32).abs() < 1e-2_f32, "{factor}x: {peak}");
+
+            oversampler.reset();
+            linear.reset();
+            assert_eq!(
+                vec![0_f32; 64],
+                round_trip(&mut oversampler, &[0_f32; 64], 64)
+            );
+        }
+    }
+}
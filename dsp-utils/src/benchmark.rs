@@ -3,7 +3,6 @@ use std::time::Duration;
 use std::time::Instant;
 
 use crate::core::file_processing::read_data;
-use crate::core::points_processing::buffer_from_vec;
 use crate::DspCoreProcessor;
 use crate::ParamsBlock;
 use crate::SingleChannelProcessor;
@@ -20,7 +19,7 @@ pub fn measure_time<SCP>(
 where
     SCP: SingleChannelProcessor,
 {
-    let mut proc: DspCoreProcessor<SCP> = DspCoreProcessor::new(params, block_size, 2);
+    let mut proc: DspCoreProcessor<SCP> = DspCoreProcessor::new(params, block_size, 2, 44100_f32);
     zero_params(&mut proc.params_block);
 
     let (l, r) = read_data(audio_path, 0, SAMPLES);
@@ -36,10 +35,8 @@ where
         a.copy_from_slice(&r[processed_samples..processed_samples + buffer_size]);
         b.copy_from_slice(&l[processed_samples..processed_samples + buffer_size]);
 
-        let mut buffer = buffer_from_vec(&mut a, &mut b);
-
         let before_process = Instant::now();
-        proc.process(&mut buffer);
+        proc.process_slices(&mut [&mut a[..], &mut b[..]]);
         timer += before_process.elapsed();
 
         processed_samples += buffer_size;
@@ -0,0 +1,145 @@
+/// What a channel carries, derived from its position in the main channel layout
+///
+/// Hosts order the channels of a layout the same way, so the role follows from the index and the number of main channels in
+/// your [`nih_plug::prelude::AudioIOLayout`]. 5.1 and 7.1 use the WAVE order, which CLAP and VST3 follow too: in 7.1 the
+/// rear channels come before the side channels. Other layouts have no named roles.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ChannelRole {
+    /// The only channel of a mono layout
+    Mono,
+    /// Front left
+    Left,
+    /// Front right
+    Right,
+    /// Front center
+    Center,
+    /// Low frequency effects
+    Lfe,
+    /// Side left in 7.1, surround left in 5.1
+    SurroundLeft,
+    /// Side right in 7.1, surround right in 5.1
+    SurroundRight,
+    /// Rear (back) left in 7.1
+    RearLeft,
+    /// Rear (back) right in 7.1
+    RearRight,
+    /// Channel of a layout without named roles
+    Other,
+}
+
+impl ChannelRole {
+    /// Role of channel `index` in a layout of `channels` main channels
+    pub fn new(index: usize, channels: usize) -> Self {
+        const SURROUND_5_1: [ChannelRole; 6] = [
+            ChannelRole::Left,
+            ChannelRole::Right,
+            ChannelRole::Center,
+            ChannelRole::Lfe,
+            ChannelRole::SurroundLeft,
+            ChannelRole::SurroundRight,
+        ];
+        const SURROUND_7_1: [ChannelRole; 8] = [
+            ChannelRole::Left,
+            ChannelRole::Right,
+            ChannelRole::Center,
+            ChannelRole::Lfe,
+            ChannelRole::RearLeft,
+            ChannelRole::RearRight,
+            ChannelRole::SurroundLeft,
+            ChannelRole::SurroundRight,
+        ];
+
+        match (channels, index) {
+            (1, 0) => Self::Mono,
+            (2 | 6, index) if index < channels => SURROUND_5_1[index],
+            (8, index) if index < channels => SURROUND_7_1[index],
+            _ => Self::Other,
+        }
+    }
+}
+
+/// The channel a [`crate::SingleChannelProcessor`] is created for
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ChannelInfo {
+    /// Index of the channel in the buffer
+    pub index: usize,
+    /// Number of channels the processor was configured with
+    pub channels: usize,
+    /// What the channel carries
+    pub role: ChannelRole,
+}
+
+impl ChannelInfo {
+    /// Info of channel `index` out of `channels`
+    pub fn new(index: usize, channels: usize) -> Self {
+        Self {
+            index,
+            channels,
+            role: ChannelRole::new(index, channels),
+        }
+    }
+
+    /// Info of channel `index` out of `channels`, when only the first `main_channels` belong to the main layout
+    ///
+    /// The roles follow the main layout, the channels beyond it are [`ChannelRole::Other`]
+    pub fn in_layout(index: usize, channels: usize, main_channels: usize) -> Self {
+        Self {
+            index,
+            channels,
+            role: ChannelRole::new(index, main_channels),
+        }
+    }
+}
+
+/// What [`crate::LinkedDspCoreProcessor`] does with host channels beyond the number it was configured with
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ChannelPolicy {
+    /// Output silence on the extra channels
+    Mute,
+    /// Pass the extra channels through unprocessed
+    ///
+    /// Channels up to the `max_channels` given to [`crate::LinkedDspCoreProcessor::with_channel_policy`] are delayed by the
+    /// latency, so they stay aligned with the processed ones. Without it, they are left untouched.
+    #[default]
+    PassThrough,
+    /// Process the extra channels like the others. With [`crate::DspCoreProcessor`] each one gets its own processor, a
+    /// [`crate::MultiChannelProcessor`] is created with more channels
+    Clone,
+    /// Return [`nih_plug::prelude::ProcessStatus::Error`] for buffers with extra channels
+    Error,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ChannelRole;
+
+    #[test]
+    fn test_roles() {
+        assert_eq!(ChannelRole::Mono, ChannelRole::new(0, 1));
+        assert_eq!(ChannelRole::Right, ChannelRole::new(1, 2));
+        assert_eq!(ChannelRole::Lfe, ChannelRole::new(3, 6));
+        assert_eq!(ChannelRole::SurroundRight, ChannelRole::new(5, 6));
+        assert_eq!(ChannelRole::Other, ChannelRole::new(2, 4));
+        assert_eq!(ChannelRole::Other, ChannelRole::new(2, 2));
+    }
+
+    // Pins the 7.1 order, the rear channels come before the side channels
+
+    #[test]
+    fn test_roles_7_1() {
+        let roles: Vec<ChannelRole> = (0..8).map(|index| ChannelRole::new(index, 8)).collect();
+        assert_eq!(
+            vec![
+                ChannelRole::Left,
+                ChannelRole::Right,
+                ChannelRole::Center,
+                ChannelRole::Lfe,
+                ChannelRole::RearLeft,
+                ChannelRole::RearRight,
+                ChannelRole::SurroundLeft,
+                ChannelRole::SurroundRight,
+            ],
+            roles
+        );
+    }
+}
@@ -0,0 +1,823 @@
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use nih_plug::params::FloatParam;
+use nih_plug::params::Params;
+use nih_plug::prelude::ProcessStatus;
+use nih_plug::prelude::SysExMessage;
+
+use crate::delay::shift_block;
+use crate::delay::DelayLine;
+use crate::delay::LookaheadBuffer;
+use crate::error::to_status;
+use crate::BlockContext;
+use crate::ChannelInfo;
+use crate::ParamsBlock;
+use crate::ProcessError;
+use crate::Sample;
+use crate::SingleChannelProcessor;
+
+/// [`Params`] holding the params of two processors, used by [`Chain`] and [`Parallel`]
+///
+/// Usually implemented by a struct with two `#[nested]` fields.
+///
+/// # Examples
+///
+/// ```no_run
+/// #[derive(Params)]
+/// struct PluginParams {
+///     #[nested(id_prefix = "gain", group = "Gain")]
+///     gain: Arc<GainParams>,
+///     #[nested(id_prefix = "clip", group = "Clip")]
+///     clip: Arc<ClipParams>,
+/// }
+///
+/// impl CombinedParams<GainParams, ClipParams> for PluginParams {
+///     fn first(&self) -> Arc<GainParams> {
+///         self.gain.clone()
+///     }
+///
+///     fn second(&self) -> Arc<ClipParams> {
+///         self.clip.clone()
+///     }
+/// }
+///
+/// // Gain followed by the clipper, each reading its own params
+/// type Processor = DspCoreProcessor<Chain<GainScp, ClipScp, PluginParams>>;
+/// ```
+pub trait CombinedParams<A: Params, B: Params>: Params {
+    /// Params of the first processor
+    fn first(&self) -> Arc<A>;
+    /// Params of the second processor
+    fn second(&self) -> Arc<B>;
+}
+
+/// [`ParamsBlock`] of two processors, built from [`CombinedParams`]
+///
+/// Both blocks are updated once per block, and each processor only receives its own.
+pub struct PairBlock<P, A: ParamsBlock, B: ParamsBlock> {
+    first: A,
+    second: B,
+    params: PhantomData<P>,
+}
+
+impl<P, A: ParamsBlock, B: ParamsBlock> PairBlock<P, A, B> {
+    /// Block of the first processor
+    pub fn first(&self) -> &A {
+        &self.first
+    }
+
+    /// Block of the second processor
+    pub fn second(&self) -> &B {
+        &self.second
+    }
+}
+
+impl<P, A, B> ParamsBlock for PairBlock<P, A, B>
+where
+    P: CombinedParams<A::Params, B::Params>,
+    A: ParamsBlock,
+    B: ParamsBlock,
+{
+    type Params = P;
+
+    fn new(params: Arc<Self::Params>, block_size: usize) -> Self {
+        Self {
+            first: A::new(params.first(), block_size),
+            second: B::new(params.second(), block_size),
+            params: PhantomData,
+        }
+    }
+
+    fn from_params(&mut self) {
+        self.first.from_params();
+        self.second.from_params();
+    }
+
+    fn partial_from_params(&mut self, block_len: usize) {
+        self.first.partial_from_params(block_len);
+        self.second.partial_from_params(block_len);
+    }
+}
+
+/// Runs `A`, and then `B` on its output
+///
+/// The latency is the sum of both, and so is the lookahead. See [`CombinedParams`] for an example.
+pub struct Chain<A, B, P, T: Sample = f32> {
+    first: A,
+    second: B,
+    temp: Vec<T>,
+    // The block followed by its upcoming samples, for running `A` ahead of `B`
+    ahead: Vec<T>,
+    // Delays the output of `A`, keeping the upcoming samples `B` looks ahead into
+    second_lookahead: LookaheadBuffer<T>,
+    params: PhantomData<P>,
+}
+
+impl<A, B, P, T> SingleChannelProcessor<T> for Chain<A, B, P, T>
+where
+    A: SingleChannelProcessor<T>,
+    B: SingleChannelProcessor<T>,
+    P: CombinedParams<
+        <A::ParamsBlock as ParamsBlock>::Params,
+        <B::ParamsBlock as ParamsBlock>::Params,
+    >,
+    T: Sample,
+{
+    type ParamsBlock = PairBlock<P, A::ParamsBlock, B::ParamsBlock>;
+
+    fn new(block_size: usize, channel: ChannelInfo, sample_rate: f32, params: Arc<P>) -> Self {
+        let first = A::new(block_size, channel, sample_rate, params.first());
+        let second = B::new(block_size, channel, sample_rate, params.second());
+
+        Self {
+            temp: vec![T::ZERO; block_size],
+            ahead: vec![T::ZERO; block_size + first.lookahead() + second.lookahead()],
+            second_lookahead: LookaheadBuffer::new(second.lookahead(), block_size),
+            first,
+            second,
+            params: PhantomData,
+        }
+    }
+
+    fn process(
+        &mut self,
+        block: &[T],
+        output: &mut [T],
+        params_block: &Self::ParamsBlock,
+    ) -> ProcessStatus {
+        self.process_with_context(
+            block,
+            output,
+            params_block,
+            &BlockContext::<T, ()>::default(),
+        )
+    }
+
+    fn process_with_context<S: SysExMessage>(
+        &mut self,
+        block: &[T],
+        output: &mut [T],
+        params_block: &Self::ParamsBlock,
+        context: &BlockContext<T, S>,
+    ) -> ProcessStatus {
+        to_status(self.try_process(block, output, params_block, context))
+    }
+
+    fn try_process<S: SysExMessage>(
+        &mut self,
+        block: &[T],
+        output: &mut [T],
+        params_block: &Self::ParamsBlock,
+        context: &BlockContext<T, S>,
+    ) -> Result<(), ProcessError> {
+        let temp = &mut self.temp[0..block.len()];
+        let first_lookahead = self.first.lookahead();
+        let second_lookahead = self.second_lookahead.lookahead();
+
+        // The first processor runs ahead by the lookahead of the second one, whose input is then delayed back, so the
+        // second one sees the upcoming samples of the first one's output. The second processor runs even if the first one
+        // failed, so its state stays in sync
+        let first = if second_lookahead == 0 {
+            let upcoming = context.lookahead();
+            let upcoming = &upcoming[0..upcoming.len().min(first_lookahead)];
+            self.first.try_process(
+                block,
+                temp,
+                &params_block.first,
+                &context.with_lookahead(upcoming),
+            )
+        } else {
+            let (block, upcoming) = shift_block(
+                &mut self.ahead,
+                block,
+                context.lookahead(),
+                second_lookahead,
+                first_lookahead,
+            );
+            self.first.try_process(
+                block,
+                temp,
+                &params_block.first,
+                &context.with_lookahead(upcoming),
+            )
+        };
+        let second = if second_lookahead == 0 {
+            self.second.try_process(
+                temp,
+                output,
+                &params_block.second,
+                &context.with_lookahead(&[]),
+            )
+        } else {
+            let (temp, upcoming) = self.second_lookahead.push(temp);
+            self.second.try_process(
+                temp,
+                output,
+                &params_block.second,
+                &context.with_lookahead(upcoming),
+            )
+        };
+        first.and(second)
+    }
+
+    fn latency(&self) -> usize {
+        self.first.latency() + self.second.latency()
+    }
+
+    fn lookahead(&self) -> usize {
+        self.first.lookahead() + self.second.lookahead()
+    }
+
+    fn tail(&self) -> usize {
+        self.first.tail().saturating_add(self.second.tail())
+    }
+
+    fn accepts_variable_block_size(&self) -> bool {
+        self.first.accepts_variable_block_size() && self.second.accepts_variable_block_size()
+    }
+
+    fn reset(&mut self) {
+        self.first.reset();
+        self.second.reset();
+        self.second_lookahead.reset();
+    }
+
+    fn suspend(&mut self) {
+        self.first.suspend();
+        self.second.suspend();
+    }
+
+    fn set_aux_inputs(&mut self, aux_input_ports: &[usize]) {
+        self.first.set_aux_inputs(aux_input_ports);
+        self.second.set_aux_inputs(aux_input_ports);
+    }
+}
+
+/// Runs `A` and `B` on the same input, and sums their outputs
+///
+/// The output of the processor with less latency is delayed, so both line up. The difference in latency is measured once,
+/// when the processor is created. The lookahead is the larger one of both.
+pub struct Parallel<A, B, P, T: Sample = f32> {
+    first: A,
+    second: B,
+    temp: Vec<T>,
+    first_delay: DelayLine<T>,
+    second_delay: DelayLine<T>,
+    params: PhantomData<P>,
+}
+
+impl<A, B, P, T> SingleChannelProcessor<T> for Parallel<A, B, P, T>
+where
+    A: SingleChannelProcessor<T>,
+    B: SingleChannelProcessor<T>,
+    P: CombinedParams<
+        <A::ParamsBlock as ParamsBlock>::Params,
+        <B::ParamsBlock as ParamsBlock>::Params,
+    >,
+    T: Sample,
+{
+    type ParamsBlock = PairBlock<P, A::ParamsBlock, B::ParamsBlock>;
+
+    fn new(block_size: usize, channel: ChannelInfo, sample_rate: f32, params: Arc<P>) -> Self {
+        let first = A::new(block_size, channel, sample_rate, params.first());
+        let second = B::new(block_size, channel, sample_rate, params.second());
+        let latency = first.latency().max(second.latency());
+
+        Self {
+            first_delay: DelayLine::new(latency - first.latency()),
+            second_delay: DelayLine::new(latency - second.latency()),
+            first,
+            second,
+            temp: vec![T::ZERO; block_size],
+            params: PhantomData,
+        }
+    }
+
+    fn process(
+        &mut self,
+        block: &[T],
+        output: &mut [T],
+        params_block: &Self::ParamsBlock,
+    ) -> ProcessStatus {
+        self.process_with_context(
+            block,
+            output,
+            params_block,
+            &BlockContext::<T, ()>::default(),
+        )
+    }
+
+    fn process_with_context<S: SysExMessage>(
+        &mut self,
+        block: &[T],
+        output: &mut [T],
+        params_block: &Self::ParamsBlock,
+        context: &BlockContext<T, S>,
+    ) -> ProcessStatus {
+        to_status(self.try_process(block, output, params_block, context))
+    }
+
+    fn try_process<S: SysExMessage>(
+        &mut self,
+        block: &[T],
+        output: &mut [T],
+        params_block: &Self::ParamsBlock,
+        context: &BlockContext<T, S>,
+    ) -> Result<(), ProcessError> {
+        let temp = &mut self.temp[0..block.len()];
+        // Both get the block delayed by the larger lookahead, and as many upcoming samples as they asked for
+        let upcoming = context.lookahead();
+        let first_upcoming = &upcoming[0..upcoming.len().min(self.first.lookahead())];
+        let second_upcoming = &upcoming[0..upcoming.len().min(self.second.lookahead())];
+
+        // Both processors and delays run even if one failed, so their state stays in sync
+        let first = self.first.try_process(
+            block,
+            output,
+            &params_block.first,
+            &context.with_lookahead(first_upcoming),
+        );
+        let second = self.second.try_process(
+            block,
+            temp,
+            &params_block.second,
+            &context.with_lookahead(second_upcoming),
+        );
+
+        self.first_delay.process(output);
+        self.second_delay.process(temp);
+        for (output, &sample) in output.iter_mut().zip(temp.iter()) {
+            *output += sample;
+        }
+
+        first.and(second)
+    }
+
+    fn latency(&self) -> usize {
+        self.first.latency() + self.first_delay.delay()
+    }
+
+    fn lookahead(&self) -> usize {
+        self.first.lookahead().max(self.second.lookahead())
+    }
+
+    fn tail(&self) -> usize {
+        self.first.tail().max(self.second.tail())
+    }
+
+    fn accepts_variable_block_size(&self) -> bool {
+        self.first.accepts_variable_block_size() && self.second.accepts_variable_block_size()
+    }
+
+    fn reset(&mut self) {
+        self.first.reset();
+        self.second.reset();
+        self.first_delay.reset();
+        self.second_delay.reset();
+    }
+
+    fn suspend(&mut self) {
+        self.first.suspend();
+        self.second.suspend();
+    }
+
+    fn set_aux_inputs(&mut self, aux_input_ports: &[usize]) {
+        self.first.set_aux_inputs(aux_input_ports);
+        self.second.set_aux_inputs(aux_input_ports);
+    }
+}
+
+/// [`Params`] of a [`DryWet`] processor, holding the params of the wet processor and the mix amount
+///
+/// # Examples
+///
+/// ```no_run
+/// #[derive(Params)]
+/// struct PluginParams {
+///     #[nested(group = "Clip")]
+///     clip: Arc<ClipParams>,
+///     #[id = "mix"]
+///     mix: FloatParam,
+/// }
+///
+/// impl DryWetParams<ClipParams> for PluginParams {
+///     fn wet(&self) -> Arc<ClipParams> {
+///         self.clip.clone()
+///     }
+///
+///     fn mix(&self) -> &FloatParam {
+///         &self.mix
+///     }
+/// }
+///
+/// type Processor = DspCoreProcessor<DryWet<ClipScp, PluginParams>>;
+/// ```
+pub trait DryWetParams<W: Params>: Params {
+    /// Params of the wet processor
+    fn wet(&self) -> Arc<W>;
+    /// Mix amount, `0.0` is fully dry and `1.0` fully wet
+    fn mix(&self) -> &FloatParam;
+}
+
+/// [`ParamsBlock`] of a [`DryWet`] processor
+pub struct DryWetBlock<P, W: ParamsBlock> {
+    params: Arc<P>,
+    wet: W,
+    /// Smoothed mix amount for every sample of the block
+    pub mix: Vec<f32>,
+}
+
+impl<P, W: ParamsBlock> DryWetBlock<P, W> {
+    /// Block of the wet processor
+    pub fn wet(&self) -> &W {
+        &self.wet
+    }
+}
+
+impl<P: DryWetParams<W::Params>, W: ParamsBlock> ParamsBlock for DryWetBlock<P, W> {
+    type Params = P;
+
+    fn new(params: Arc<Self::Params>, block_size: usize) -> Self {
+        Self {
+            wet: W::new(params.wet(), block_size),
+            params,
+            mix: vec![0_f32; block_size],
+        }
+    }
+
+    fn from_params(&mut self) {
+        let block_len = self.mix.len();
+        self.partial_from_params(block_len);
+    }
+
+    fn partial_from_params(&mut self, block_len: usize) {
+        self.wet.partial_from_params(block_len);
+        self.params
+            .mix()
+            .smoothed
+            .next_block(&mut self.mix, block_len);
+    }
+}
+
+/// Mixes the output of `W` with its input
+///
+/// The dry signal is delayed by the latency of `W`, measured once when the processor is created, so there is no comb
+/// filtering between the two. The lookahead of `W` is forwarded, it delays the input of both.
+pub struct DryWet<W, P, T: Sample = f32> {
+    wet: W,
+    dry: Vec<T>,
+    dry_delay: DelayLine<T>,
+    params: PhantomData<P>,
+}
+
+impl<W, P, T> SingleChannelProcessor<T> for DryWet<W, P, T>
+where
+    W: SingleChannelProcessor<T>,
+    P: DryWetParams<<W::ParamsBlock as ParamsBlock>::Params>,
+    T: Sample,
+{
+    type ParamsBlock = DryWetBlock<P, W::ParamsBlock>;
+
+    fn new(block_size: usize, channel: ChannelInfo, sample_rate: f32, params: Arc<P>) -> Self {
+        let wet = W::new(block_size, channel, sample_rate, params.wet());
+        Self {
+            dry_delay: DelayLine::new(wet.latency()),
+            wet,
+            dry: vec![T::ZERO; block_size],
+            params: PhantomData,
+        }
+    }
+
+    fn process(
+        &mut self,
+        block: &[T],
+        output: &mut [T],
+        params_block: &Self::ParamsBlock,
+    ) -> ProcessStatus {
+        self.process_with_context(
+            block,
+            output,
+            params_block,
+            &BlockContext::<T, ()>::default(),
+        )
+    }
+
+    fn process_with_context<S: SysExMessage>(
+        &mut self,
+        block: &[T],
+        output: &mut [T],
+        params_block: &Self::ParamsBlock,
+        context: &BlockContext<T, S>,
+    ) -> ProcessStatus {
+        to_status(self.try_process(block, output, params_block, context))
+    }
+
+    fn try_process<S: SysExMessage>(
+        &mut self,
+        block: &[T],
+        output: &mut [T],
+        params_block: &Self::ParamsBlock,
+        context: &BlockContext<T, S>,
+    ) -> Result<(), ProcessError> {
+        let result = self
+            .wet
+            .try_process(block, output, &params_block.wet, context);
+
+        let dry = &mut self.dry[0..block.len()];
+        dry.copy_from_slice(block);
+        self.dry_delay.process(dry);
+
+        for ((output, &dry), &mix) in output.iter_mut().zip(dry.iter()).zip(&params_block.mix) {
+            let mix = T::from_f32(mix);
+            *output = *output * mix + dry * (T::ONE - mix);
+        }
+
+        result
+    }
+
+    fn latency(&self) -> usize {
+        self.dry_delay.delay()
+    }
+
+    fn lookahead(&self) -> usize {
+        self.wet.lookahead()
+    }
+
+    fn tail(&self) -> usize {
+        self.wet.tail()
+    }
+
+    fn accepts_variable_block_size(&self) -> bool {
+        self.wet.accepts_variable_block_size()
+    }
+
+    fn reset(&mut self) {
+        self.wet.reset();
+        self.dry_delay.reset();
+    }
+
+    fn suspend(&mut self) {
+        self.wet.suspend();
+    }
+
+    fn set_aux_inputs(&mut self, aux_input_ports: &[usize]) {
+        self.wet.set_aux_inputs(aux_input_ports);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use nih_plug::buffer::Buffer;
+    #[cfg(not(feature = "test"))]
+    use nih_plug::params::FloatParam;
+    use nih_plug::params::Params;
+    #[cfg(not(feature = "test"))]
+    use nih_plug::prelude::FloatRange;
+    use nih_plug::prelude::{ProcessStatus, SysExMessage};
+
+    use super::{Chain, CombinedParams, Parallel};
+    #[cfg(not(feature = "test"))]
+    use super::{DryWet, DryWetParams};
+    use crate::delay::DelayLine;
+    use crate::{BlockContext, ChannelInfo, DspCoreProcessor, ParamsBlock, SingleChannelProcessor};
+
+    #[derive(Params)]
+    struct Settings {
+        delay: usize,
+        offset: f32,
+    }
+    struct SettingsBlock {
+        offset: f32,
+    }
+    impl ParamsBlock for SettingsBlock {
+        type Params = Settings;
+        fn new(params: Arc<Self::Params>, _block_size: usize) -> Self {
+            Self {
+                offset: params.offset,
+            }
+        }
+        fn from_params(&mut self) {}
+    }
+
+    // Delays the input and adds an offset, both taken from its own params
+    struct Offset {
+        delay: DelayLine<f32>,
+    }
+    impl SingleChannelProcessor for Offset {
+        type ParamsBlock = SettingsBlock;
+        fn new(
+            _block_size: usize,
+            _channel: ChannelInfo,
+            _sample_rate: f32,
+            params: Arc<Settings>,
+        ) -> Self {
+            Self {
+                delay: DelayLine::new(params.delay),
+            }
+        }
+        fn process(
+            &mut self,
+            block: &[f32],
+            output: &mut [f32],
+            params_block: &Self::ParamsBlock,
+        ) -> ProcessStatus {
+            output.copy_from_slice(block);
+            self.delay.process(output);
+            for sample in output.iter_mut() {
+                *sample += params_block.offset;
+            }
+            ProcessStatus::Normal
+        }
+        fn latency(&self) -> usize {
+            self.delay.delay()
+        }
+    }
+
+    // Adds the offset, looking ahead by the delay from its params. Checks the upcoming samples it saw start the next block
+    struct Peek {
+        lookahead: usize,
+        upcoming: Vec<f32>,
+    }
+    impl SingleChannelProcessor for Peek {
+        type ParamsBlock = SettingsBlock;
+        fn new(
+            _block_size: usize,
+            _channel: ChannelInfo,
+            _sample_rate: f32,
+            params: Arc<Settings>,
+        ) -> Self {
+            Self {
+                lookahead: params.delay,
+                upcoming: Vec::with_capacity(params.delay),
+            }
+        }
+        fn process(
+            &mut self,
+            _block: &[f32],
+            _output: &mut [f32],
+            _params_block: &Self::ParamsBlock,
+        ) -> ProcessStatus {
+            ProcessStatus::Error("No lookahead")
+        }
+        fn process_with_context<S: SysExMessage>(
+            &mut self,
+            block: &[f32],
+            output: &mut [f32],
+            params_block: &Self::ParamsBlock,
+            context: &BlockContext<f32, S>,
+        ) -> ProcessStatus {
+            assert_eq!(self.lookahead, context.lookahead().len());
+            assert_eq!(self.upcoming, block[0..self.upcoming.len()]);
+            self.upcoming.clear();
+            self.upcoming.extend_from_slice(context.lookahead());
+
+            for (output, sample) in output.iter_mut().zip(block) {
+                *output = sample + params_block.offset;
+            }
+            ProcessStatus::Normal
+        }
+        fn lookahead(&self) -> usize {
+            self.lookahead
+        }
+    }
+
+    #[derive(Params)]
+    struct Pair {
+        first: Arc<Settings>,
+        second: Arc<Settings>,
+    }
+    impl CombinedParams<Settings, Settings> for Pair {
+        fn first(&self) -> Arc<Settings> {
+            self.first.clone()
+        }
+        fn second(&self) -> Arc<Settings> {
+            self.second.clone()
+        }
+    }
+
+    fn pair(first: (usize, f32), second: (usize, f32)) -> Arc<Pair> {
+        Arc::new(Pair {
+            first: Arc::new(Settings {
+                delay: first.0,
+                offset: first.1,
+            }),
+            second: Arc::new(Settings {
+                delay: second.0,
+                offset: second.1,
+            }),
+        })
+    }
+
+    // Processes a ramp in buffers of 30 samples, returning the output and the latency
+    fn process_ramp<SCP: SingleChannelProcessor>(
+        params: Arc<<SCP::ParamsBlock as ParamsBlock>::Params>,
+    ) -> (Vec<f32>, usize) {
+        let mut proc: DspCoreProcessor<SCP> = DspCoreProcessor::new(params, 16, 1, 0_f32);
+
+        let mut output: Vec<f32> = (0..200).map(|i| i as f32).collect();
+        for block in output.chunks_mut(30) {
+            let mut buffer = Buffer::default();
+            unsafe {
+                buffer.set_slices(block.len(), |slices| {
+                    *slices = vec![block];
+                })
+            }
+            proc.process(&mut buffer);
+        }
+        (output, proc.latency())
+    }
+
+    // Ramp delayed by `latency`, scaled and offset
+    fn expected(latency: usize, scale: f32, offset: f32) -> Vec<f32> {
+        (0..200)
+            .map(|i| {
+                if i < latency {
+                    0_f32
+                } else {
+                    (i - latency) as f32 * scale + offset
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_chain() {
+        let (output, latency) =
+            process_ramp::<Chain<Offset, Offset, Pair>>(pair((3, 1_f32), (2, 2_f32)));
+        assert_eq!(16 + 5, latency);
+        // The offset of the first processor is delayed by the second one
+        assert_eq!(expected(latency, 1_f32, 3_f32)[40..], output[40..]);
+    }
+
+    #[test]
+    fn test_parallel() {
+        let (output, latency) =
+            process_ramp::<Parallel<Offset, Offset, Pair>>(pair((5, 1_f32), (0, 2_f32)));
+        assert_eq!(16 + 5, latency);
+        assert_eq!(expected(latency, 2_f32, 3_f32)[40..], output[40..]);
+    }
+
+    // Makes sure the lookahead of the wrapped processors is forwarded, and delays the output by its amount
+
+    #[test]
+    fn test_lookahead() {
+        let (output, latency) =
+            process_ramp::<Chain<Peek, Peek, Pair>>(pair((4, 1_f32), (2, 2_f32)));
+        assert_eq!(16 + 6, latency);
+        assert_eq!(expected(latency, 1_f32, 3_f32)[40..], output[40..]);
+
+        let (output, latency) =
+            process_ramp::<Chain<Offset, Peek, Pair>>(pair((3, 1_f32), (2, 2_f32)));
+        assert_eq!(16 + 3 + 2, latency);
+        assert_eq!(expected(latency, 1_f32, 3_f32)[40..], output[40..]);
+
+        let (output, latency) =
+            process_ramp::<Parallel<Peek, Offset, Pair>>(pair((4, 1_f32), (3, 2_f32)));
+        assert_eq!(16 + 4 + 3, latency);
+        assert_eq!(expected(latency, 2_f32, 3_f32)[40..], output[40..]);
+    }
+
+    // The mix is smoothed in `from_params`, which is not called with the `test` feature
+    #[cfg(not(feature = "test"))]
+    #[derive(Params)]
+    struct Mix {
+        wet: Arc<Settings>,
+        mix: FloatParam,
+    }
+    #[cfg(not(feature = "test"))]
+    impl DryWetParams<Settings> for Mix {
+        fn wet(&self) -> Arc<Settings> {
+            self.wet.clone()
+        }
+        fn mix(&self) -> &FloatParam {
+            &self.mix
+        }
+    }
+
+    #[cfg(not(feature = "test"))]
+    #[test]
+    fn test_dry_wet() {
+        let params = Arc::new(Mix {
+            wet: Arc::new(Settings {
+                delay: 7,
+                offset: 1_f32,
+            }),
+            mix: FloatParam::new(
+                "Mix",
+                0.25_f32,
+                FloatRange::Linear {
+                    min: 0_f32,
+                    max: 1_f32,
+                },
+            ),
+        });
+        params.mix.smoothed.reset(0.25_f32);
+
+        let (output, latency) = process_ramp::<DryWet<Offset, Mix>>(params);
+        assert_eq!(16 + 7, latency);
+        assert_eq!(expected(latency, 1_f32, 0.25_f32)[40..], output[40..]);
+    }
+}
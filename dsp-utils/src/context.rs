@@ -0,0 +1,173 @@
+use nih_plug::prelude::NoteEvent;
+use nih_plug::prelude::SysExMessage;
+
+use crate::Sample;
+use crate::TransportInfo;
+
+/// Note event, with timing relative to the start of the block being processed
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockEvent<S> {
+    /// Index of the sample in the block the event belongs to
+    ///
+    /// Use this rather than [`NoteEvent::timing`], which is relative to the host buffer the event came from
+    pub timing: usize,
+    /// The event itself
+    pub event: NoteEvent<S>,
+}
+
+/// Additional data available for the block being processed
+///
+/// Passed to [`crate::SingleChannelProcessor::process_with_context`] and
+/// [`crate::MultiChannelProcessor::process_with_context`]. All blocks and events in it cover the same span of time as the
+/// main block, unless the processor has a lookahead, see [`crate::SingleChannelProcessor::lookahead`].
+pub struct BlockContext<'a, T: Sample = f32, S: SysExMessage = ()> {
+    channel: usize,
+    aux_inputs: &'a [Vec<Vec<T>>],
+    events: &'a [BlockEvent<S>],
+    lookahead: &'a [T],
+    // Factor the event timings are multiplied by, for processors running at a higher sample rate
+    rate: usize,
+    transport: TransportInfo,
+}
+
+impl<T: Sample, S: SysExMessage> Clone for BlockContext<'_, T, S> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: Sample, S: SysExMessage> Copy for BlockContext<'_, T, S> {}
+
+impl<T: Sample, S: SysExMessage> Default for BlockContext<'_, T, S> {
+    fn default() -> Self {
+        Self {
+            channel: 0,
+            aux_inputs: &[],
+            events: &[],
+            lookahead: &[],
+            rate: 1,
+            transport: TransportInfo::default(),
+        }
+    }
+}
+
+impl<'a, T: Sample, S: SysExMessage> BlockContext<'a, T, S> {
+    pub(crate) fn new(
+        aux_inputs: &'a [Vec<Vec<T>>],
+        events: &'a [BlockEvent<S>],
+        transport: TransportInfo,
+    ) -> Self {
+        Self {
+            channel: 0,
+            aux_inputs,
+            events,
+            lookahead: &[],
+            rate: 1,
+            transport,
+        }
+    }
+
+    // Context for a single channel processor of given channel
+    pub(crate) fn for_channel(&self, channel: usize) -> Self {
+        Self { channel, ..*self }
+    }
+
+    // Context with the upcoming samples of the channel being processed
+    pub(crate) fn with_lookahead<'b>(&self, lookahead: &'b [T]) -> BlockContext<'b, T, S>
+    where
+        'a: 'b,
+    {
+        BlockContext {
+            channel: self.channel,
+            aux_inputs: self.aux_inputs,
+            events: self.events,
+            lookahead,
+            rate: self.rate,
+            transport: self.transport,
+        }
+    }
+
+    // Context for a processor running `factor` times faster, with the auxiliary inputs upsampled by the caller
+    pub(crate) fn oversampled<'b>(
+        &self,
+        aux_inputs: &'b [Vec<Vec<T>>],
+        factor: usize,
+    ) -> BlockContext<'b, T, S>
+    where
+        'a: 'b,
+    {
+        let mut transport = self.transport;
+        transport.sample_rate *= factor as f32;
+        transport.pos_samples = transport.pos_samples.map(|pos| pos * factor as i64);
+
+        BlockContext {
+            channel: self.channel,
+            aux_inputs,
+            events: self.events,
+            lookahead: &[],
+            rate: self.rate * factor,
+            transport,
+        }
+    }
+
+    /// Index of the channel being processed
+    ///
+    /// Always `0` for [`crate::MultiChannelProcessor`]
+    pub fn channel(&self) -> usize {
+        self.channel
+    }
+
+    /// Number of auxiliary inputs
+    pub fn aux_input_count(&self) -> usize {
+        self.aux_inputs.len()
+    }
+
+    /// Blocks of all channels of the auxiliary input `port`
+    ///
+    /// Returns `None` if the port was not set up with [`crate::LinkedDspCoreProcessor::with_aux_inputs`]
+    pub fn aux_input(&self, port: usize) -> Option<&'a [Vec<T>]> {
+        self.aux_inputs
+            .get(port)
+            .map(|channels| channels.as_slice())
+    }
+
+    /// Block of the sidechain (first auxiliary input) for the channel being processed
+    ///
+    /// Returns `None` if there is no sidechain, or it has fewer channels then the main input
+    pub fn sidechain(&self) -> Option<&'a [T]> {
+        self.aux_inputs
+            .first()?
+            .get(self.channel)
+            .map(|block| block.as_slice())
+    }
+
+    /// Samples of the channel being processed that follow the block, see [`crate::SingleChannelProcessor::lookahead`]
+    ///
+    /// Holds exactly as many samples as the processor asked for, and is empty for [`crate::MultiChannelProcessor`]. These are
+    /// the undelayed input samples, the rest of the context ends with them.
+    pub fn lookahead(&self) -> &'a [T] {
+        self.lookahead
+    }
+
+    /// Note events of the block, in the order they were received
+    ///
+    /// Their [`BlockEvent::timing`] is the index of the sample in the block they belong to, so they line up with the audio
+    /// they were sent together with. Every processor, and every channel, receives the same events. Inside
+    /// [`crate::Oversampled`] the timings are in oversampled samples.
+    pub fn events(&self) -> impl ExactSizeIterator<Item = BlockEvent<S>> + 'a {
+        let rate = self.rate;
+        self.events.iter().map(move |event| BlockEvent {
+            timing: event.timing * rate,
+            event: event.event.clone(),
+        })
+    }
+
+    /// Transport of the block, with the position of its first sample
+    ///
+    /// The position is the one the host reported for the block's input samples, so it already accounts for the delay of the
+    /// processing. Only available when using [`crate::LinkedDspCoreProcessor::process_with_context`], otherwise the transport
+    /// is stopped.
+    pub fn transport(&self) -> &TransportInfo {
+        &self.transport
+    }
+}
@@ -1,41 +1,36 @@
-use nih_plug::buffer::Buffer;
 use nih_plug::util;
 
-// Get RMS value from buffer
-pub(crate) fn get_rms(buf: &Buffer) -> f32 {
-    let slice = buf.as_slice_immutable();
+// Get RMS value from channels
+pub(crate) fn get_rms(channels: &[&mut [f32]]) -> f32 {
+    let samples = channels[0].len();
     let mut sum = 0_f32;
-    for channel in slice {
-        for i in 0..buf.samples() {
-            sum += channel[i].abs();
+    for channel in channels {
+        for sample in channel.iter() {
+            sum += sample.abs();
         }
     }
-    (sum / (buf.samples() as f32 * buf.channels() as f32)).sqrt()
+    (sum / (samples as f32 * channels.len() as f32)).sqrt()
 }
 
-// Get peak from buffer
-pub(crate) fn get_peak(buf: &Buffer) -> f32 {
-    let slice = buf.as_slice_immutable();
+// Get peak from channels
+pub(crate) fn get_peak(channels: &[&mut [f32]]) -> f32 {
     let mut max = 0_f32;
 
-    for channel in slice {
-        for i in 0..buf.samples() {
-            max = max.max(channel[i].abs())
+    for channel in channels {
+        for sample in channel.iter() {
+            max = max.max(sample.abs())
         }
     }
     max
 }
 
-// Get mean loudness from buffer
-pub(crate) fn get_mean_loudness(buf: &mut Buffer) -> f32 {
+// Get mean loudness from the first two channels
+pub(crate) fn get_mean_loudness(channels: &[&mut [f32]]) -> f32 {
     let mut i = 0_f32;
     let mut max_sum = 0_f32;
-    for block in buf.iter_blocks(2048) {
+    for (l, r) in channels[0].chunks(2048).zip(channels[1].chunks(2048)) {
         let mut max_val = std::f32::MIN;
-        for el in block.1.get(0).unwrap() {
-            max_val = max_val.max(el.abs());
-        }
-        for el in block.1.get(1).unwrap() {
+        for el in l.iter().chain(r) {
             max_val = max_val.max(el.abs());
         }
         max_sum += max_val;
@@ -45,19 +40,6 @@ pub(crate) fn get_mean_loudness(buf: &mut Buffer) -> f32 {
     max_sum / i
 }
 
-// Convert vectors to a buffer
-pub(crate) fn buffer_from_vec<'a>(l: &'a mut Vec<f32>, r: &'a mut Vec<f32>) -> Buffer<'a> {
-    assert_eq!(l.len(), r.len());
-    let mut buf = Buffer::default();
-
-    unsafe {
-        buf.set_slices(l.len(), |slices| {
-            *slices = vec![l, r];
-        })
-    }
-    buf
-}
-
 // Converts all y coordinates of the points into dB rather then gain
 pub(crate) fn convert_points_to_db(points: &mut Vec<(f32, f32)>, multiplier: f32) {
     for i in 0..points.len() {
@@ -5,6 +5,7 @@ use nih_plug::prelude::FloatRange;
 use nih_plug::prelude::SmoothingStyle;
 use nih_plug::util;
 
+use crate::ChannelInfo;
 use crate::ParamsBlock;
 use crate::SingleChannelProcessor;
 
@@ -57,7 +58,12 @@ pub(crate) struct GainScp {}
 impl SingleChannelProcessor for GainScp {
     type ParamsBlock = GainParamsBlock;
 
-    fn new(_block_size: usize, _sample_rate: f32, _params: Arc<GainParams>) -> Self {
+    fn new(
+        _block_size: usize,
+        _channel: ChannelInfo,
+        _sample_rate: f32,
+        _params: Arc<GainParams>,
+    ) -> Self {
         Self {}
     }
 
@@ -142,7 +148,12 @@ pub(crate) struct ClipScp {}
 impl SingleChannelProcessor for ClipScp {
     type ParamsBlock = ClipParamsBlock;
 
-    fn new(_block_size: usize, _sample_rate: f32, _params: Arc<ClipParams>) -> Self {
+    fn new(
+        _block_size: usize,
+        _channel: ChannelInfo,
+        _sample_rate: f32,
+        _params: Arc<ClipParams>,
+    ) -> Self {
         Self {}
     }
 
@@ -0,0 +1,110 @@
+use crate::Sample;
+
+// Fixed delay, used to keep signal paths with different latencies aligned
+pub(crate) struct DelayLine<T: Sample> {
+    buffer: Vec<T>,
+    pos: usize,
+}
+
+impl<T: Sample> DelayLine<T> {
+    pub(crate) fn new(delay: usize) -> Self {
+        Self {
+            buffer: vec![T::ZERO; delay],
+            pos: 0,
+        }
+    }
+
+    pub(crate) fn delay(&self) -> usize {
+        self.buffer.len()
+    }
+
+    // Delays the block in place
+    pub(crate) fn process(&mut self, block: &mut [T]) {
+        if self.buffer.is_empty() {
+            return;
+        }
+        for sample in block.iter_mut() {
+            *sample = self.next(*sample);
+        }
+    }
+
+    // Delays a single sample
+    pub(crate) fn next(&mut self, sample: T) -> T {
+        if self.buffer.is_empty() {
+            return sample;
+        }
+        let delayed = std::mem::replace(&mut self.buffer[self.pos], sample);
+        self.pos = (self.pos + 1) % self.buffer.len();
+        delayed
+    }
+
+    // Changes the delay and clears the buffer, only reallocating if it grows beyond the initial delay
+    pub(crate) fn set_delay(&mut self, delay: usize) {
+        self.buffer.resize(delay, T::ZERO);
+        self.reset();
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.buffer.fill(T::ZERO);
+        self.pos = 0;
+    }
+}
+
+// Delays blocks by a fixed number of samples, keeping the samples that follow each block available
+pub(crate) struct LookaheadBuffer<T: Sample> {
+    // Samples still to be processed, followed by the latest block
+    buffer: Vec<T>,
+    lookahead: usize,
+    // Length of the last block, whose samples were already returned
+    processed: usize,
+}
+
+impl<T: Sample> LookaheadBuffer<T> {
+    pub(crate) fn new(lookahead: usize, block_size: usize) -> Self {
+        Self {
+            buffer: vec![T::ZERO; lookahead + block_size],
+            lookahead,
+            processed: 0,
+        }
+    }
+
+    pub(crate) fn lookahead(&self) -> usize {
+        self.lookahead
+    }
+
+    // Appends the block, returning the block delayed by the lookahead and the samples following it
+    pub(crate) fn push(&mut self, block: &[T]) -> (&[T], &[T]) {
+        let len = block.len();
+        self.buffer
+            .copy_within(self.processed..self.processed + self.lookahead, 0);
+        self.buffer[self.lookahead..self.lookahead + len].copy_from_slice(block);
+        self.processed = len;
+        self.buffer[..len + self.lookahead].split_at(len)
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.buffer.fill(T::ZERO);
+        self.processed = 0;
+    }
+}
+
+// Copies `block` followed by its `upcoming` samples into `buffer`, and returns the block moved `shift` samples later
+// together with the `lookahead` samples following it. Upcoming samples that were not provided are silent, so processors
+// called without a lookahead still get full blocks
+pub(crate) fn shift_block<'a, T: Sample>(
+    buffer: &'a mut [T],
+    block: &[T],
+    upcoming: &[T],
+    shift: usize,
+    lookahead: usize,
+) -> (&'a [T], &'a [T]) {
+    let len = block.len();
+    let upcoming = &upcoming[0..upcoming.len().min(shift + lookahead)];
+    let buffer = &mut buffer[0..len + shift + lookahead];
+    buffer[0..len].copy_from_slice(block);
+    buffer[len..len + upcoming.len()].copy_from_slice(upcoming);
+    buffer[len + upcoming.len()..].fill(T::ZERO);
+
+    let buffer: &'a [T] = buffer;
+    buffer[shift..].split_at(len)
+}
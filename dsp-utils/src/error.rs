@@ -0,0 +1,127 @@
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use nih_plug::prelude::ProcessStatus;
+
+/// Error that stopped a block from being processed
+///
+/// Processors fail with it from [`crate::SingleChannelProcessor::try_process`]. It is only turned into a
+/// [`ProcessStatus::Error`] when it reaches the host.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ProcessError {
+    /// The host buffer had no channels
+    NoChannels,
+    /// The host buffer had more channels than configured, with [`crate::ChannelPolicy::Error`]
+    TooManyChannels,
+    /// The channels or auxiliary inputs differ in length, or interleaved samples do not fill whole frames
+    MismatchedLengths,
+    /// The processor returned [`ProcessStatus::Error`] with the message
+    Processor(&'static str),
+    /// The processor cannot work with the value of a parameter, with the name of the parameter
+    InvalidParam(&'static str),
+    /// The processor produced NaN or infinite samples, e.g. because a filter became unstable
+    ///
+    /// [`crate::LinkedDspCoreProcessor`] checks the output of every block for them.
+    NotFinite,
+    /// The processor needs an auxiliary input that is not connected, with the index of the port
+    ///
+    /// Returned when fewer auxiliary inputs are passed than set up with [`crate::LinkedDspCoreProcessor::with_aux_inputs`].
+    MissingAuxInput(usize),
+}
+
+impl ProcessError {
+    /// Message passed on to the host
+    pub fn message(&self) -> &'static str {
+        match self {
+            Self::NoChannels => "No channels",
+            Self::TooManyChannels => "More channels than configured",
+            Self::MismatchedLengths => "Channels differ in length",
+            Self::Processor(message) => message,
+            Self::InvalidParam(_) => "Invalid parameter value",
+            Self::NotFinite => "Processor produced non finite samples",
+            Self::MissingAuxInput(_) => "Auxiliary input not connected",
+        }
+    }
+}
+
+impl From<ProcessError> for ProcessStatus {
+    fn from(error: ProcessError) -> Self {
+        ProcessStatus::Error(error.message())
+    }
+}
+
+// Status passed on for the result of a block
+pub(crate) fn to_status(result: Result<(), ProcessError>) -> ProcessStatus {
+    match result {
+        Ok(()) => ProcessStatus::Normal,
+        Err(error) => error.into(),
+    }
+}
+
+// Result of a block, from the status a processor returned
+pub(crate) fn from_status(status: ProcessStatus) -> Result<(), ProcessError> {
+    match status {
+        ProcessStatus::Error(message) => Err(ProcessError::Processor(message)),
+        _ => Ok(()),
+    }
+}
+
+/// What [`crate::LinkedDspCoreProcessor`] does with a block the processor failed on
+///
+/// Whatever the policy, the rest of the host buffer is still processed, so the buffered samples stay consistent and the
+/// next buffer lines up.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ErrorPolicy {
+    /// Output silence for the block, and return [`ProcessStatus::Error`] to the host once the buffer is done
+    #[default]
+    Propagate,
+    /// Output silence for the block
+    Silence,
+    /// Output the unprocessed block, delayed by the latency of the processor
+    Bypass,
+}
+
+/// Number of failed blocks, shared with e.g. the editor
+///
+/// Get it from [`crate::LinkedDspCoreProcessor::error_counters`]. Updated without locking, so it can be read from any thread.
+#[derive(Default, Debug)]
+pub struct ErrorCounters {
+    errors: AtomicUsize,
+    recovered: AtomicUsize,
+    dropped_events: AtomicUsize,
+}
+
+impl ErrorCounters {
+    /// Number of blocks the processor failed on
+    pub fn errors(&self) -> usize {
+        self.errors.load(Ordering::Relaxed)
+    }
+
+    /// Number of failed blocks that were silenced or bypassed instead of reported to the host
+    pub fn recovered(&self) -> usize {
+        self.recovered.load(Ordering::Relaxed)
+    }
+
+    /// Number of note events dropped because too many were pending, see [`crate::LinkedDspCoreProcessor::queue_event`]
+    pub fn dropped_events(&self) -> usize {
+        self.dropped_events.load(Ordering::Relaxed)
+    }
+
+    /// Set all counters back to zero
+    pub fn clear(&self) {
+        self.errors.store(0, Ordering::Relaxed);
+        self.recovered.store(0, Ordering::Relaxed);
+        self.dropped_events.store(0, Ordering::Relaxed);
+    }
+
+    pub(crate) fn count(&self, policy: ErrorPolicy) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+        if policy != ErrorPolicy::Propagate {
+            self.recovered.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub(crate) fn count_dropped_event(&self) {
+        self.dropped_events.fetch_add(1, Ordering::Relaxed);
+    }
+}
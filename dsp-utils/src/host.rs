@@ -0,0 +1,169 @@
+use nih_plug::buffer::Buffer;
+
+use crate::read_samples;
+use crate::write_samples;
+use crate::Sample;
+
+// Audio received from the host, either as one slice per channel or interleaved
+pub(crate) trait HostInput {
+    fn channels(&self) -> usize;
+
+    fn samples(&self) -> usize;
+
+    // Copies `block.len()` samples of `channel`, starting at `index`, into `block`
+    fn read<T: Sample>(&self, channel: usize, index: usize, block: &mut [T]);
+
+    // Index of the last audible sample in any of the first `channels` channels
+    fn last_sound(&self, channels: usize) -> Option<usize>;
+
+    // Whether every channel holds exactly `samples()` samples
+    fn is_consistent(&self) -> bool {
+        true
+    }
+}
+
+// Level below which samples count as silence, -120 dB
+const SILENCE: f32 = 1e-6_f32;
+
+fn audible(sample: f32) -> bool {
+    sample.abs() > SILENCE
+}
+
+// Audio sent back to the host
+pub(crate) trait HostOutput: HostInput {
+    // Copies `block` into `channel`, starting at `index`
+    fn write<T: Sample>(&mut self, channel: usize, index: usize, block: &[T]);
+
+    // Replaces every sample of `channel` with `f` of it
+    fn map(&mut self, channel: usize, f: impl FnMut(f32) -> f32);
+}
+
+impl<C: AsRef<[f32]>> HostInput for [C] {
+    fn channels(&self) -> usize {
+        self.len()
+    }
+
+    fn samples(&self) -> usize {
+        self.first().map_or(0, |channel| channel.as_ref().len())
+    }
+
+    fn read<T: Sample>(&self, channel: usize, index: usize, block: &mut [T]) {
+        read_samples(block, &self[channel].as_ref()[index..index + block.len()]);
+    }
+
+    fn last_sound(&self, channels: usize) -> Option<usize> {
+        self.iter()
+            .take(channels)
+            .filter_map(|channel| channel.as_ref().iter().rposition(|&sample| audible(sample)))
+            .max()
+    }
+
+    fn is_consistent(&self) -> bool {
+        let samples = self.samples();
+        self.iter().all(|channel| channel.as_ref().len() == samples)
+    }
+}
+
+impl<C: AsRef<[f32]> + AsMut<[f32]>> HostOutput for [C] {
+    fn write<T: Sample>(&mut self, channel: usize, index: usize, block: &[T]) {
+        write_samples(
+            &mut self[channel].as_mut()[index..index + block.len()],
+            block,
+        );
+    }
+
+    fn map(&mut self, channel: usize, mut f: impl FnMut(f32) -> f32) {
+        for sample in self[channel].as_mut().iter_mut() {
+            *sample = f(*sample);
+        }
+    }
+}
+
+impl HostInput for Buffer<'_> {
+    fn channels(&self) -> usize {
+        Buffer::channels(self)
+    }
+
+    fn samples(&self) -> usize {
+        Buffer::samples(self)
+    }
+
+    fn read<T: Sample>(&self, channel: usize, index: usize, block: &mut [T]) {
+        self.as_slice_immutable().read(channel, index, block);
+    }
+
+    fn last_sound(&self, channels: usize) -> Option<usize> {
+        self.as_slice_immutable().last_sound(channels)
+    }
+}
+
+impl<H: HostInput + ?Sized> HostInput for &H {
+    fn channels(&self) -> usize {
+        (**self).channels()
+    }
+
+    fn samples(&self) -> usize {
+        (**self).samples()
+    }
+
+    fn read<T: Sample>(&self, channel: usize, index: usize, block: &mut [T]) {
+        (**self).read(channel, index, block);
+    }
+
+    fn last_sound(&self, channels: usize) -> Option<usize> {
+        (**self).last_sound(channels)
+    }
+
+    fn is_consistent(&self) -> bool {
+        (**self).is_consistent()
+    }
+}
+
+// Interleaved samples, with `channels` samples per frame
+pub(crate) struct Interleaved<'a> {
+    pub(crate) samples: &'a mut [f32],
+    pub(crate) channels: usize,
+}
+
+impl HostInput for Interleaved<'_> {
+    fn channels(&self) -> usize {
+        self.channels
+    }
+
+    fn samples(&self) -> usize {
+        self.samples.len().checked_div(self.channels).unwrap_or(0)
+    }
+
+    fn read<T: Sample>(&self, channel: usize, index: usize, block: &mut [T]) {
+        let frames = self.samples[index * self.channels..].chunks_exact(self.channels);
+        for (sample, frame) in block.iter_mut().zip(frames) {
+            *sample = T::from_f32(frame[channel]);
+        }
+    }
+
+    fn last_sound(&self, channels: usize) -> Option<usize> {
+        let channels = channels.min(self.channels);
+        self.samples
+            .chunks_exact(self.channels)
+            .rposition(|frame| frame[0..channels].iter().any(|&sample| audible(sample)))
+    }
+
+    fn is_consistent(&self) -> bool {
+        self.channels > 0 && self.samples.len().is_multiple_of(self.channels)
+    }
+}
+
+impl HostOutput for Interleaved<'_> {
+    fn write<T: Sample>(&mut self, channel: usize, index: usize, block: &[T]) {
+        let frames = self.samples[index * self.channels..].chunks_exact_mut(self.channels);
+        for (&sample, frame) in block.iter().zip(frames) {
+            frame[channel] = sample.to_f32();
+        }
+    }
+
+    fn map(&mut self, channel: usize, mut f: impl FnMut(f32) -> f32) {
+        for frame in self.samples.chunks_exact_mut(self.channels) {
+            frame[channel] = f(frame[channel]);
+        }
+    }
+}
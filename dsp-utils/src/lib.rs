@@ -1,7 +1,7 @@
 // TODO:
 // Window structure that takes in some trait object
 // Rewrite dct to use said trait object
-// Add more algorithms, fft, ifft
+// Add more algorithms
 // Some utils for wavetables, linear interpolation and so on
 
 //! DSP utility functions for nih_plug
@@ -9,7 +9,8 @@
 //! Includes [`Buffer`] abstraction using [`DspCoreProcessor`], as well as some general DSP functions and structures.
 //!
 //! [`DspCoreProcessor`] enables you to write one structure that handles DSP, with constant block size, independent of engine
-//! or DAW settings.
+//! or DAW settings. If your processing needs to see all channels at once (e.g. linked compressors or mid/side processing),
+//! implement [`MultiChannelProcessor`] and use [`LinkedDspCoreProcessor`] instead.
 //!
 //! # Examples
 //!
@@ -86,7 +87,12 @@
 //!     // The type in which the param data will be provided
 //!     type ParamsBlock = PluginParamsBlock;
 //!
-//!     fn new(_block_size: usize) -> Self {
+//!     fn new(
+//!         _block_size: usize,
+//!         _channel: ChannelInfo,
+//!         _sample_rate: f32,
+//!         _params: Arc<PluginParams>,
+//!     ) -> Self {
 //!         Self{}
 //!     }
 //!
@@ -106,11 +112,25 @@
 //! }
 //! ```
 
+use std::num::NonZeroU32;
+use std::ops::Range;
 use std::sync::Arc;
 
 use nih_plug::buffer::Buffer;
+use nih_plug::params::BoolParam;
 use nih_plug::params::Params;
+use nih_plug::prelude::AuxiliaryBuffers;
+use nih_plug::prelude::InitContext;
+use nih_plug::prelude::NoteEvent;
+use nih_plug::prelude::Plugin;
+use nih_plug::prelude::ProcessContext;
 use nih_plug::prelude::ProcessStatus;
+use nih_plug::prelude::SysExMessage;
+
+// Lets the derive macros refer to `dsp_utils` from inside this crate
+extern crate self as dsp_utils;
+
+pub use dsp_utils_derive::ParamsBlock;
 
 mod core;
 
@@ -126,15 +146,108 @@ mod benchmark;
 #[cfg(feature = "benchmark")]
 pub use benchmark::*;
 
+/// Harness to test your processors the way hosts drive them, see [`testing::test_processor`]
+///
+/// Only available with the `testing` feature, enable it for your dev-dependencies. `testing_alloc_warnings` reports
+/// allocations with a panic instead of aborting, but also turns the allocation checks of nih_plug into warnings.
+#[cfg(feature = "testing")]
+pub mod testing;
+
+/// Offline rendering of audio through your processors, see [`render::render_file`]
+///
+/// Only available with the `render` feature.
+#[cfg(feature = "render")]
+pub mod render;
+
 mod misc;
 pub use misc::*;
 
+mod context;
+pub use context::BlockContext;
+pub use context::BlockEvent;
+
+mod transport;
+pub use transport::TransportInfo;
+
+mod modulation;
+pub use modulation::EnvelopeFollower;
+pub use modulation::Lfo;
+pub use modulation::LfoShape;
+pub use modulation::Modulation;
+pub use modulation::ModulationContext;
+pub use modulation::ModulationSource;
+pub use modulation::Rate;
+pub use modulation::StepSequencer;
+
+mod shared;
+pub use shared::ring_buffer;
+pub use shared::AtomicF32;
+pub use shared::Consumer;
+pub use shared::Producer;
+
+mod voices;
+pub use voices::Expression;
+pub use voices::VoiceEvent;
+pub use voices::VoiceManager;
+pub use voices::VoiceProcessor;
+pub use voices::VoiceStealing;
+
+mod matrix;
+pub use matrix::ChannelMatrix;
+
+mod error;
+use error::from_status;
+use error::to_status;
+pub use error::ErrorCounters;
+pub use error::ErrorPolicy;
+pub use error::ProcessError;
+
+mod channels;
+pub use channels::ChannelInfo;
+pub use channels::ChannelPolicy;
+pub use channels::ChannelRole;
+
+mod sample;
+pub use sample::read_samples;
+pub use sample::write_samples;
+pub use sample::Sample;
+
+mod oversampled;
+pub use oversampled::hold;
+pub use oversampled::LinearPhase;
+pub use oversampled::MinimumPhase;
+pub use oversampled::Oversampled;
+pub use oversampled::OversampledBlock;
+pub use oversampled::OversamplingParamsBlock;
+pub use oversampled::OversamplingPhase;
+
+mod combinators;
+pub use combinators::Chain;
+pub use combinators::CombinedParams;
+pub use combinators::DryWet;
+pub use combinators::DryWetBlock;
+pub use combinators::DryWetParams;
+pub use combinators::PairBlock;
+pub use combinators::Parallel;
+
+mod delay;
+use delay::DelayLine;
+use delay::LookaheadBuffer;
+
+mod host;
+use host::HostInput;
+use host::HostOutput;
+use host::Interleaved;
+
 pub mod algorithms;
 
 /// A trait used to process a single channel.
 ///
 /// Always receives blocks of size defined on new call of [`DspCoreProcessor`]
 ///
+/// `T` is the [`Sample`] type the blocks are processed in, `f32` by default. Implement `SingleChannelProcessor<f64>` for
+/// double precision processing, and use `DspCoreProcessor<YourProcessor, f64>`.
+///
 /// # Examples
 ///
 /// ```no_run
@@ -145,7 +258,12 @@ pub mod algorithms;
 /// impl SingleChannelProcessor for ApplyGain {
 ///     type ParamsBlock = ImplementsParamsBlock;
 ///
-///     fn new(block_size: usize) -> Self {
+///     fn new(
+///         block_size: usize,
+///         _channel: ChannelInfo,
+///         _sample_rate: f32,
+///         _params: Arc<ImplementsParams>,
+///     ) -> Self {
 ///         // Allocate whatever data is needed here
 ///         Self { block_size }
 ///     }
@@ -169,7 +287,7 @@ pub mod algorithms;
 ///     }
 /// }
 /// ```
-pub trait SingleChannelProcessor {
+pub trait SingleChannelProcessor<T: Sample = f32> {
     /// Type that is used as to store blocks of your parameter data.
     ///
     /// Must implement [`ParamsBlock`] trait. It will be updated every processed block. The parameters should be extracted by setting
@@ -194,9 +312,11 @@ pub trait SingleChannelProcessor {
     /// Initialize the processor struct.
     ///
     /// Allocate all data you need here. `block_size` is the length of all blocks in the process function. It is also what
-    /// you set [`DspCoreProcessor`] to divide the blocks supplied by DAW to.
+    /// you set [`DspCoreProcessor`] to divide the blocks supplied by DAW to. `channel` tells which channel the processor
+    /// owns, e.g. so a stereo processor can treat left and right differently.
     fn new(
         block_size: usize,
+        channel: ChannelInfo,
         sample_rate: f32,
         params: Arc<<Self::ParamsBlock as ParamsBlock>::Params>,
     ) -> Self;
@@ -208,10 +328,111 @@ pub trait SingleChannelProcessor {
     /// to process the block.
     fn process(
         &mut self,
-        block: &[f32],
-        output: &mut [f32],
+        block: &[T],
+        output: &mut [T],
         params_block: &Self::ParamsBlock,
     ) -> ProcessStatus;
+
+    /// Process single block, with access to additional data of the block.
+    ///
+    /// Works like [`Self::process`], but also receives a [`BlockContext`], holding e.g. the auxiliary input blocks and note events
+    /// for the same span of time. Defaults to calling [`Self::process`], override it if you need any of the data.
+    fn process_with_context<S: SysExMessage>(
+        &mut self,
+        block: &[T],
+        output: &mut [T],
+        params_block: &Self::ParamsBlock,
+        _context: &BlockContext<T, S>,
+    ) -> ProcessStatus {
+        self.process(block, output, params_block)
+    }
+
+    /// Process single block, with access to additional data of the block, failing with a typed [`ProcessError`].
+    ///
+    /// This is what [`DspCoreProcessor`] calls. Defaults to calling [`Self::process_with_context`], turning
+    /// [`ProcessStatus::Error`] into [`ProcessError::Processor`]. Override it to fail with e.g.
+    /// [`ProcessError::InvalidParam`], and implement [`Self::process`] by calling it.
+    fn try_process<S: SysExMessage>(
+        &mut self,
+        block: &[T],
+        output: &mut [T],
+        params_block: &Self::ParamsBlock,
+        context: &BlockContext<T, S>,
+    ) -> Result<(), ProcessError> {
+        from_status(self.process_with_context(block, output, params_block, context))
+    }
+
+    /// Latency in samples added by the processor itself.
+    ///
+    /// Return a non zero value if your processing delays the signal, e.g. because of lookahead or linear phase filtering. It is
+    /// added on top of the `block_size` samples of delay generated by [`DspCoreProcessor`], see [`LinkedDspCoreProcessor::latency`]
+    fn latency(&self) -> usize {
+        0
+    }
+
+    /// Number of upcoming samples the processor needs to see, e.g. so a limiter can react before a peak arrives.
+    ///
+    /// [`DspCoreProcessor`] delays the input by this many samples, and passes the samples following each block in
+    /// [`BlockContext::lookahead`]. The lookahead is added to the reported latency, so do not include it in
+    /// [`Self::latency`]. Read when the processor is created or reconfigured.
+    ///
+    /// Only the audio is delayed. The [`ParamsBlock`], and the events, auxiliary inputs and transport of the
+    /// [`BlockContext`] describe the newest samples, the last `block.len()` samples of the block followed by
+    /// [`BlockContext::lookahead`]. They lead the block by the lookahead, so e.g. a sidechain detector gets the same head
+    /// start as the audio one. Add the lookahead to the event timings if you need them relative to the block.
+    fn lookahead(&self) -> usize {
+        0
+    }
+
+    /// Number of samples the processor keeps producing sound after the input went silent, e.g. the decay of a reverb.
+    ///
+    /// Counted from the end of the latency, so a lookahead does not need to be added. Return `usize::MAX` if the processor
+    /// can produce sound indefinitely, e.g. a self oscillating filter. See [`LinkedDspCoreProcessor::tail`]
+    fn tail(&self) -> usize {
+        0
+    }
+
+    /// Whether the processor can handle blocks shorter than `block_size`.
+    ///
+    /// If this returns `true`, blocks passed to [`Self::process`] can be of any length up to `block_size`. This allows
+    /// [`LatencyMode::ZeroLatency`] to process without delay for any host buffer size.
+    fn accepts_variable_block_size(&self) -> bool {
+        false
+    }
+
+    /// Clear the internal state, e.g. filter memory or delay lines.
+    ///
+    /// Called by [`LinkedDspCoreProcessor::reset`], which you should call from [`nih_plug::prelude::Plugin::reset`]. After
+    /// this the processor should behave as if it was just created. Must not allocate. Does nothing by default.
+    fn reset(&mut self) {}
+
+    /// Processing stopped, called by [`LinkedDspCoreProcessor::suspend`].
+    ///
+    /// Use it to stop background work or release resources, that are not needed until processing resumes. The host will
+    /// reset the plugin before processing again. Does nothing by default.
+    fn suspend(&mut self) {}
+
+    /// Set up for the auxiliary inputs, with the number of channels of every port.
+    ///
+    /// Called by [`LinkedDspCoreProcessor::with_aux_inputs`], and again after the processor was reconfigured. Runs outside of
+    /// processing, so allocate the buffers you need for the auxiliary inputs here. Does nothing by default.
+    fn set_aux_inputs(&mut self, _aux_input_ports: &[usize]) {}
+
+    /// Change the `block_size` and `sample_rate` the processor runs at.
+    ///
+    /// Called by [`LinkedDspCoreProcessor::reconfigure`]. The default implementation replaces the processor with a new one,
+    /// override it if you want to keep some of the state or allocations.
+    fn reconfigure(
+        &mut self,
+        block_size: usize,
+        channel: ChannelInfo,
+        sample_rate: f32,
+        params: Arc<<Self::ParamsBlock as ParamsBlock>::Params>,
+    ) where
+        Self: Sized,
+    {
+        *self = Self::new(block_size, channel, sample_rate, params);
+    }
 }
 
 /// Trait for a structure holding blocks of [`Params`] data
@@ -219,6 +440,9 @@ pub trait SingleChannelProcessor {
 /// This should be implemented by having a field of type `Vec<f32>` for every param in your [`Self::Params`] type, that you want to
 /// access in [`SingleChannelProcessor`]
 ///
+/// Instead of writing it by hand, you can place `#[derive(ParamsBlock)]` on your [`Params`] struct, which generates a
+/// `{YourParams}Block` struct implementing this trait, see [`dsp_utils_derive::ParamsBlock`]
+///
 /// # Examples
 ///
 /// ```no_run
@@ -256,7 +480,350 @@ pub trait ParamsBlock {
     /// Allocate the data here. Be sure to save params variable as a field in your ParamsBlock struct
     fn new(params: Arc<Self::Params>, block_size: usize) -> Self;
     /// Update the blocks of params, filling them from [`nih_plug::prelude::Params`]
+    ///
+    /// Called once per block, before any channel is processed. All channels of the block share the same values, so smoothers
+    /// advance by exactly `block_size` samples.
     fn from_params(&mut self);
+    /// Update the first `block_len` samples of the blocks of params, filling them from [`nih_plug::prelude::Params`]
+    ///
+    /// Only called for blocks shorter than `block_size`, which happens when the processor accepts variable block sizes in
+    /// [`LatencyMode::ZeroLatency`]. Defaults to [`Self::from_params`], override it so the smoothers advance by `block_len`
+    /// samples only.
+    fn partial_from_params(&mut self, _block_len: usize) {
+        self.from_params();
+    }
+}
+
+/// A trait used to process all channels of a block at once.
+///
+/// Unlike [`SingleChannelProcessor`], which sees every channel in isolation, this receives the blocks of every channel for the
+/// same span of time. Use it for linked processing, e.g. stereo compressors, mid/side tools or stereo wideners. Drive it with
+/// [`LinkedDspCoreProcessor`], which gives it the same constant block size guarantee.
+///
+/// # Examples
+///
+/// ```no_run
+/// struct SwapChannels {}
+///
+/// impl MultiChannelProcessor for SwapChannels {
+///     type ParamsBlock = ImplementsParamsBlock;
+///
+///     fn new(
+///         _block_size: usize,
+///         _channels: usize,
+///         _sample_rate: f32,
+///         _params: Arc<ImplementsParams>,
+///     ) -> Self {
+///         Self {}
+///     }
+///
+///     fn process(
+///         &mut self,
+///         blocks: &[Vec<f32>],
+///         outputs: &mut [Vec<f32>],
+///         _params_block: &Self::ParamsBlock,
+///     ) -> ProcessStatus {
+///         // Every block has the same length and covers the same span of time
+///         for channel in 0..blocks.len() {
+///             outputs[channel].copy_from_slice(&blocks[blocks.len() - 1 - channel]);
+///         }
+///         ProcessStatus::Normal
+///     }
+/// }
+/// ```
+pub trait MultiChannelProcessor<T: Sample = f32> {
+    /// Type that is used as to store blocks of your parameter data, see [`SingleChannelProcessor::ParamsBlock`]
+    type ParamsBlock: ParamsBlock;
+
+    /// Initialize the processor struct.
+    ///
+    /// Allocate all data you need here. `block_size` is the length of all blocks in the process function, `channels` is the
+    /// maximum number of channels that will be passed to it.
+    fn new(
+        block_size: usize,
+        channels: usize,
+        sample_rate: f32,
+        params: Arc<<Self::ParamsBlock as ParamsBlock>::Params>,
+    ) -> Self;
+
+    /// Process single block of every channel.
+    ///
+    /// `blocks` holds one block per channel, all of the same length and all covering the same span of time. The length is
+    /// always `block_size`, unless [`Self::accepts_variable_block_size`] returns `true`. The processed
+    /// samples should be written into `outputs`, which has the same layout. The number of channels can be lower than the one
+    /// given in [`MultiChannelProcessor::new`] if the host supplies fewer channels.
+    fn process(
+        &mut self,
+        blocks: &[Vec<T>],
+        outputs: &mut [Vec<T>],
+        params_block: &Self::ParamsBlock,
+    ) -> ProcessStatus;
+
+    /// Process single block of every channel, with access to additional data of the block.
+    ///
+    /// See [`SingleChannelProcessor::process_with_context`]. Defaults to calling [`Self::process`].
+    fn process_with_context<S: SysExMessage>(
+        &mut self,
+        blocks: &[Vec<T>],
+        outputs: &mut [Vec<T>],
+        params_block: &Self::ParamsBlock,
+        _context: &BlockContext<T, S>,
+    ) -> ProcessStatus {
+        self.process(blocks, outputs, params_block)
+    }
+
+    /// Process single block of every channel, failing with a typed [`ProcessError`].
+    ///
+    /// See [`SingleChannelProcessor::try_process`]. Defaults to calling [`Self::process_with_context`].
+    fn try_process<S: SysExMessage>(
+        &mut self,
+        blocks: &[Vec<T>],
+        outputs: &mut [Vec<T>],
+        params_block: &Self::ParamsBlock,
+        context: &BlockContext<T, S>,
+    ) -> Result<(), ProcessError> {
+        from_status(self.process_with_context(blocks, outputs, params_block, context))
+    }
+
+    /// Latency in samples added by the processor itself, see [`SingleChannelProcessor::latency`]
+    fn latency(&self) -> usize {
+        0
+    }
+
+    /// Number of samples the processor keeps producing sound after the input went silent, see
+    /// [`SingleChannelProcessor::tail`]
+    fn tail(&self) -> usize {
+        0
+    }
+
+    /// Whether the processor can handle blocks shorter than `block_size`, see
+    /// [`SingleChannelProcessor::accepts_variable_block_size`]
+    fn accepts_variable_block_size(&self) -> bool {
+        false
+    }
+
+    /// Clear the internal state, see [`SingleChannelProcessor::reset`]
+    fn reset(&mut self) {}
+
+    /// Processing stopped, see [`SingleChannelProcessor::suspend`]
+    fn suspend(&mut self) {}
+
+    /// Set up for the auxiliary inputs, see [`SingleChannelProcessor::set_aux_inputs`]
+    fn set_aux_inputs(&mut self, _aux_input_ports: &[usize]) {}
+
+    /// Initialize the processor for `channels` channels, when only the first `main_channels` belong to the main layout
+    ///
+    /// Used by [`ChannelPolicy::Clone`] to process the extra host channels too. Defaults to [`Self::new`], override it if
+    /// the processor depends on the layout.
+    fn with_extra_channels(
+        block_size: usize,
+        channels: usize,
+        _main_channels: usize,
+        sample_rate: f32,
+        params: Arc<<Self::ParamsBlock as ParamsBlock>::Params>,
+    ) -> Self
+    where
+        Self: Sized,
+    {
+        Self::new(block_size, channels, sample_rate, params)
+    }
+
+    /// Change the `block_size` and `sample_rate` the processor runs at, see [`SingleChannelProcessor::reconfigure`]
+    ///
+    /// `channels` is the same as the one given in [`MultiChannelProcessor::new`].
+    fn reconfigure(
+        &mut self,
+        block_size: usize,
+        channels: usize,
+        sample_rate: f32,
+        params: Arc<<Self::ParamsBlock as ParamsBlock>::Params>,
+    ) where
+        Self: Sized,
+    {
+        *self = Self::new(block_size, channels, sample_rate, params);
+    }
+}
+
+/// [`MultiChannelProcessor`] that processes every channel with its own [`SingleChannelProcessor`].
+///
+/// This is what [`DspCoreProcessor`] uses under the hood, you should not need to use it directly.
+pub struct PerChannel<SCP, T: Sample = f32> {
+    channel_processor: Vec<SCP>,
+    // Delays the input of processors with a lookahead, empty buffers for the rest
+    lookahead: Vec<LookaheadBuffer<T>>,
+    // Channels of the main layout, the channel roles are taken from it
+    main_channels: usize,
+}
+
+// Lookahead buffers of the processors, sized for `block_size`
+fn lookahead_buffers<T: Sample, SCP: SingleChannelProcessor<T>>(
+    channel_processor: &[SCP],
+    block_size: usize,
+) -> Vec<LookaheadBuffer<T>> {
+    channel_processor
+        .iter()
+        .map(|processor| match processor.lookahead() {
+            0 => LookaheadBuffer::new(0, 0),
+            lookahead => LookaheadBuffer::new(lookahead, block_size),
+        })
+        .collect()
+}
+
+impl<T: Sample, SCP: SingleChannelProcessor<T>> MultiChannelProcessor<T> for PerChannel<SCP, T> {
+    type ParamsBlock = SCP::ParamsBlock;
+
+    fn new(
+        block_size: usize,
+        channels: usize,
+        sample_rate: f32,
+        params: Arc<<Self::ParamsBlock as ParamsBlock>::Params>,
+    ) -> Self {
+        Self::with_extra_channels(block_size, channels, channels, sample_rate, params)
+    }
+
+    fn with_extra_channels(
+        block_size: usize,
+        channels: usize,
+        main_channels: usize,
+        sample_rate: f32,
+        params: Arc<<Self::ParamsBlock as ParamsBlock>::Params>,
+    ) -> Self {
+        let channel_processor: Vec<SCP> = (0..channels)
+            .map(|index| {
+                SCP::new(
+                    block_size,
+                    ChannelInfo::in_layout(index, channels, main_channels),
+                    sample_rate,
+                    params.clone(),
+                )
+            })
+            .collect();
+        Self {
+            lookahead: lookahead_buffers(&channel_processor, block_size),
+            channel_processor,
+            main_channels,
+        }
+    }
+
+    fn process(
+        &mut self,
+        blocks: &[Vec<T>],
+        outputs: &mut [Vec<T>],
+        params_block: &Self::ParamsBlock,
+    ) -> ProcessStatus {
+        self.process_with_context(
+            blocks,
+            outputs,
+            params_block,
+            &BlockContext::<T, ()>::default(),
+        )
+    }
+
+    fn process_with_context<S: SysExMessage>(
+        &mut self,
+        blocks: &[Vec<T>],
+        outputs: &mut [Vec<T>],
+        params_block: &Self::ParamsBlock,
+        context: &BlockContext<T, S>,
+    ) -> ProcessStatus {
+        to_status(self.try_process(blocks, outputs, params_block, context))
+    }
+
+    // Every channel is processed even if one fails, so their state stays in sync. The first error is returned
+    fn try_process<S: SysExMessage>(
+        &mut self,
+        blocks: &[Vec<T>],
+        outputs: &mut [Vec<T>],
+        params_block: &Self::ParamsBlock,
+        context: &BlockContext<T, S>,
+    ) -> Result<(), ProcessError> {
+        let mut result = Ok(());
+        for (channel, (((processor, lookahead), block), output)) in self
+            .channel_processor
+            .iter_mut()
+            .zip(self.lookahead.iter_mut())
+            .zip(blocks)
+            .zip(outputs.iter_mut())
+            .enumerate()
+        {
+            let context = context.for_channel(channel);
+            // Only the audio is delayed, the params and the context lead it by the lookahead
+            let channel_result = if lookahead.lookahead() == 0 {
+                processor.try_process(block, output, params_block, &context)
+            } else {
+                let (block, upcoming) = lookahead.push(block);
+                processor.try_process(
+                    block,
+                    output,
+                    params_block,
+                    &context.with_lookahead(upcoming),
+                )
+            };
+            result = result.and(channel_result);
+        }
+        result
+    }
+
+    fn latency(&self) -> usize {
+        self.channel_processor
+            .iter()
+            .map(|processor| processor.latency() + processor.lookahead())
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn tail(&self) -> usize {
+        self.channel_processor
+            .iter()
+            .map(|processor| processor.tail())
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn accepts_variable_block_size(&self) -> bool {
+        self.channel_processor
+            .iter()
+            .all(|processor| processor.accepts_variable_block_size())
+    }
+
+    fn reset(&mut self) {
+        for processor in self.channel_processor.iter_mut() {
+            processor.reset();
+        }
+        for lookahead in self.lookahead.iter_mut() {
+            lookahead.reset();
+        }
+    }
+
+    fn suspend(&mut self) {
+        for processor in self.channel_processor.iter_mut() {
+            processor.suspend();
+        }
+    }
+
+    fn set_aux_inputs(&mut self, aux_input_ports: &[usize]) {
+        for processor in self.channel_processor.iter_mut() {
+            processor.set_aux_inputs(aux_input_ports);
+        }
+    }
+
+    fn reconfigure(
+        &mut self,
+        block_size: usize,
+        channels: usize,
+        sample_rate: f32,
+        params: Arc<<Self::ParamsBlock as ParamsBlock>::Params>,
+    ) {
+        for (index, processor) in self.channel_processor.iter_mut().enumerate() {
+            processor.reconfigure(
+                block_size,
+                ChannelInfo::in_layout(index, channels, self.main_channels),
+                sample_rate,
+                params.clone(),
+            );
+        }
+        self.lookahead = lookahead_buffers(&self.channel_processor, block_size);
+    }
 }
 
 /// Struct that splits blocks into given `block_size` and processes them with defined SingleChannelProcessor.
@@ -264,8 +831,8 @@ pub trait ParamsBlock {
 /// In your plugin struct set your processor field type as `Option<DspCoreProcessor<YourSingleChannelProcessor>>`, initially set
 /// it to `None` and initialize it in `initialize` function
 ///
-/// `block_size` given in the [`DspCoreProcessor::new`], will also be the value of how many samples of delay are generated from
-/// this part of the dsp system. That is because the number of samples in a [`Buffer`] does not need to be divisible by
+/// `block_size` given in the [`LinkedDspCoreProcessor::new`], will also be the value of how many samples of delay are generated
+/// from this part of the dsp system. That is because the number of samples in a [`Buffer`] does not need to be divisible by
 /// `block_size`, thus we need to prevent overflow/underflow of samples in each [`SingleChannelProcessor::process`] call.
 ///
 /// # Examples
@@ -292,11 +859,11 @@ pub trait ParamsBlock {
 ///     fn initialize(
 ///         &mut self,
 ///         audio_io_layout: &AudioIOLayout,
-///         _buffer_config: &BufferConfig,
-///         _context: &mut impl InitContext<Self>,
+///         buffer_config: &BufferConfig,
+///         context: &mut impl InitContext<Self>,
 ///     ) -> bool {
 ///         // In initialize we create the processor
-///         self.dsp = Some(DspCoreProcessor::new(
+///         let mut dsp = DspCoreProcessor::new(
 ///             // Params
 ///             self.params.clone(),
 ///             // block_size, size to which to split the incoming blocks
@@ -308,7 +875,11 @@ pub trait ParamsBlock {
 ///                     return false;
 ///                 }
 ///             },
-///         ));
+///             buffer_config.sample_rate,
+///         );
+///         // Let the host compensate for the delay
+///         dsp.init_latency(context);
+///         self.dsp = Some(dsp);
 ///         true
 ///     }
 ///
@@ -317,10 +888,11 @@ pub trait ParamsBlock {
 ///         &mut self,
 ///         buffer: &mut Buffer,
 ///         _aux: &mut AuxiliaryBuffers,
-///         _context: &mut impl ProcessContext<Self>,
+///         context: &mut impl ProcessContext<Self>,
 ///     ) -> ProcessStatus {
 ///         // After a check, process buffer
 ///         if let Some(processor) = &mut self.dsp {
+///             processor.update_latency(context);
 ///             processor.process(buffer)
 ///         } else {
 ///             ProcessStatus::Error("DSP data not initialized")
@@ -329,141 +901,877 @@ pub trait ParamsBlock {
 ///     [...]
 /// }
 /// ```
-pub struct DspCoreProcessor<SCP: SingleChannelProcessor> {
-    channel_processor: Vec<SCP>,
+pub type DspCoreProcessor<SCP, T = f32, S = ()> = LinkedDspCoreProcessor<PerChannel<SCP, T>, T, S>;
+
+// Number of pending note events that can be stored without reallocating
+const EVENT_CAPACITY: usize = 1024;
+
+// Picks the bypass param out of the params of the plugin
+type BypassParam<P> = fn(&P) -> &BoolParam;
+
+/// Way in which [`LinkedDspCoreProcessor`] aligns host buffers to blocks
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LatencyMode {
+    /// Always delay the signal by `block_size` samples, so any host buffer size can be split into full blocks
+    Buffered,
+    /// Process the blocks in place, without any delay.
+    ///
+    /// This only works if the host buffer size is a multiple of `block_size`, or if the processor accepts variable block sizes.
+    /// The first time a host buffer does not line up with `block_size`, the processor falls back to [`LatencyMode::Buffered`]
+    /// until the next [`LinkedDspCoreProcessor::reset`], and the latency changes, so make sure to call
+    /// [`LinkedDspCoreProcessor::update_latency`] when using this mode.
+    ZeroLatency,
+}
+
+/// Struct that splits blocks into given `block_size` and processes all channels of them with defined MultiChannelProcessor.
+///
+/// Works the same way as [`DspCoreProcessor`], with the same amount of delay, but every [`MultiChannelProcessor::process`]
+/// call receives the blocks of all channels for the same span of time.
+///
+/// `T` is the [`Sample`] type of the processor, the host buffers are converted from and to it. `S` is the
+/// [`nih_plug::prelude::Plugin::SysExMessage`] type of your plugin, only needed if you want to receive note events through
+/// [`Self::process_with_context`].
+pub struct LinkedDspCoreProcessor<
+    MCP: MultiChannelProcessor<T>,
+    T: Sample = f32,
+    S: SysExMessage = (),
+> {
+    processor: MCP,
 
     overflow: usize,
-    temp: Vec<f32>,
-    buffer: Vec<Vec<f32>>,
-    params_block: SCP::ParamsBlock,
+    temp: Vec<Vec<T>>,
+    buffer: Vec<Vec<T>>,
+    aux_temp: Vec<Vec<Vec<T>>>,
+    events: Vec<BlockEvent<S>>,
+    block_events: Vec<BlockEvent<S>>,
+    // Transport at the first sample of the current block
+    transport: TransportInfo,
+    params_block: MCP::ParamsBlock,
+    params: Arc<<MCP::ParamsBlock as ParamsBlock>::Params>,
 
     block_size: usize,
     channels: usize,
+    latency_mode: LatencyMode,
+    in_place: bool,
+    reported_latency: Option<u32>,
+    channel_matrix: ChannelMatrix,
+    channel_policy: ChannelPolicy,
+    // Delays the host channels beyond `channels` with ChannelPolicy::PassThrough
+    extra_channels: Vec<DelayLine<f32>>,
+    error_policy: ErrorPolicy,
+    error_counters: Arc<ErrorCounters>,
+    last_error: Option<ProcessError>,
+    // Delays the input of each channel with ErrorPolicy::Bypass or a bypass param, so it lines up with the output
+    dry_delays: Vec<DelayLine<T>>,
+    // Number of silent input samples since the last non silent one, `usize::MAX` if nothing was heard since the start
+    silent_samples: usize,
+    bypass: Option<BypassParam<<MCP::ParamsBlock as ParamsBlock>::Params>>,
+    bypass_fade_ms: f32,
+    // Amount of dry signal in the output, 0 when processing and 1 when bypassed
+    bypass_mix: f32,
+    modulation: Modulation<MCP::ParamsBlock>,
 }
 
-impl<SCP: SingleChannelProcessor> DspCoreProcessor<SCP> {
+impl<MCP: MultiChannelProcessor<T>, T: Sample, S: SysExMessage> LinkedDspCoreProcessor<MCP, T, S> {
     /// Initialize the struct.
     ///
-    /// `block_size` should be the number of samples per block, that you want the SingleChannelProcessor
-    /// to get each time [`SingleChannelProcessor::process`] is called
+    /// `block_size` should be the number of samples per block, that you want the processor to get each time its `process`
+    /// function is called
     ///
     /// `channels` can be lower/higher then the actual channels amount. If that happens the lower value is chosen as the
-    /// amount of channels. This cannot be zero
-
+    /// amount of channels, extra host channels are handled by the [`ChannelPolicy`]. This cannot be zero. Pass the main
+    /// channel count of your [`nih_plug::prelude::AudioIOLayout`], so processors get the right [`ChannelRole`]
     pub fn new(
-        params: Arc<<<SCP as SingleChannelProcessor>::ParamsBlock as ParamsBlock>::Params>,
+        params: Arc<<<MCP as MultiChannelProcessor<T>>::ParamsBlock as ParamsBlock>::Params>,
         block_size: usize,
         channels: usize,
         sample_rate: f32,
     ) -> Self {
         Self {
-            channel_processor: (0..channels)
-                .into_iter()
-                .map(|_| SCP::new(block_size, sample_rate, params.clone()))
-                .collect(),
-            overflow: block_size,
-            temp: vec![0_f32; block_size],
-            buffer: Vec::from_iter(std::iter::repeat_n(
-                Vec::from_iter(std::iter::repeat_n(0_f32, block_size)),
-                channels,
-            )),
+            processor: MCP::new(block_size, channels, sample_rate, params.clone()),
+            overflow: 0,
+            temp: vec![vec![T::ZERO; block_size]; channels],
+            buffer: vec![vec![T::ZERO; block_size]; channels],
+            aux_temp: Vec::new(),
+            events: Vec::with_capacity(EVENT_CAPACITY),
+            block_events: Vec::with_capacity(EVENT_CAPACITY),
+            transport: TransportInfo {
+                sample_rate,
+                ..Default::default()
+            },
 
-            params_block: SCP::ParamsBlock::new(params, block_size),
+            params_block: MCP::ParamsBlock::new(params.clone(), block_size),
+            params,
             block_size,
             channels,
+            latency_mode: LatencyMode::Buffered,
+            in_place: false,
+            reported_latency: None,
+            channel_matrix: ChannelMatrix::LeftRight,
+            channel_policy: ChannelPolicy::PassThrough,
+            extra_channels: Vec::new(),
+            error_policy: ErrorPolicy::Propagate,
+            error_counters: Arc::new(ErrorCounters::default()),
+            last_error: None,
+            dry_delays: Vec::new(),
+            silent_samples: usize::MAX,
+            bypass: None,
+            bypass_fade_ms: 0_f32,
+            bypass_mix: 0_f32,
+            modulation: Modulation::new(),
         }
     }
 
-    /// Process the `buffer` using supplied processor type.
+    /// Clear all buffered audio and pending events, and reset the processor, see [`SingleChannelProcessor::reset`].
     ///
-    /// The `buffer` will be split into channels, and then into blocks of given length that will be processed by
-    /// [`SingleChannelProcessor`]. The struct stores overflow samples and will prevent any half processed blocks
-    pub fn process(&mut self, buffer: &mut Buffer) -> ProcessStatus {
-        let samples = buffer.samples();
-        let channels = buffer.channels().min(self.channels);
-        let slice = buffer.as_slice();
-
-        if slice.len() == 0 {
-            return ProcessStatus::Error("No channels");
+    /// Call it from [`nih_plug::prelude::Plugin::reset`], so no stale audio is played after e.g. seeking. Does not allocate.
+    pub fn reset(&mut self) {
+        self.overflow = 0;
+        self.silent_samples = usize::MAX;
+        for block in self.temp.iter_mut().chain(self.buffer.iter_mut()) {
+            block.fill(T::ZERO);
         }
+        for block in self.aux_temp.iter_mut().flatten() {
+            block.fill(T::ZERO);
+        }
+        self.events.clear();
+        self.block_events.clear();
+        for delay in self.extra_channels.iter_mut() {
+            delay.reset();
+        }
+        for delay in self.dry_delays.iter_mut() {
+            delay.reset();
+        }
+        self.bypass_mix = self.bypass_target();
+        self.modulation.reset();
+        // Try processing in place again, the host buffers may line up from now on
+        self.in_place = self.latency_mode == LatencyMode::ZeroLatency;
 
-        let mut index = self.block_size - self.overflow;
-        if index != 0 {
-            for channel in 0..channels {
-                self.temp[0..self.overflow]
-                    .copy_from_slice(&self.buffer[channel][0..self.overflow]);
+        self.processor.reset();
+    }
 
-                self.temp[self.overflow..self.block_size]
-                    .copy_from_slice(&slice[channel][0..index]);
-                slice[channel][0..index]
-                    .copy_from_slice(&self.buffer[channel][self.overflow..self.block_size]);
+    /// Notify the processor that processing stopped, see [`SingleChannelProcessor::suspend`].
+    ///
+    /// Call it from [`nih_plug::prelude::Plugin::deactivate`].
+    pub fn suspend(&mut self) {
+        self.processor.suspend();
+    }
 
-                #[cfg(not(feature = "test"))]
-                self.params_block.from_params();
+    /// Change the `block_size` and `sample_rate`, keeping the rest of the configuration.
+    ///
+    /// Reallocates all buffers, so call it from [`nih_plug::prelude::Plugin::initialize`], not while processing. The buffered
+    /// audio is dropped, and the processor is reconfigured, see [`SingleChannelProcessor::reconfigure`]. The latency changes
+    /// with the `block_size`, report it again with [`Self::init_latency`].
+    pub fn reconfigure(&mut self, block_size: usize, sample_rate: f32) {
+        self.block_size = block_size;
+        self.transport.sample_rate = sample_rate;
+        self.params_block = MCP::ParamsBlock::new(self.params.clone(), block_size);
+        self.processor
+            .reconfigure(block_size, self.channels, sample_rate, self.params.clone());
+        self.allocate();
+    }
 
-                match self.channel_processor[channel].process(
-                    &self.temp,
-                    &mut self.buffer[channel],
-                    &self.params_block,
-                ) {
-                    ProcessStatus::Error(e) => return ProcessStatus::Error(e),
-                    _ => {}
-                };
-            }
-            self.overflow = self.block_size;
+    // Reallocates the buffers for the current block size, channels and processor, dropping the buffered audio
+    fn allocate(&mut self) {
+        let block_size = self.block_size;
+        self.overflow = 0;
+        self.temp = vec![vec![T::ZERO; block_size]; self.channels];
+        self.buffer = vec![vec![T::ZERO; block_size]; self.channels];
+        for port in self.aux_temp.iter_mut() {
+            *port = vec![vec![T::ZERO; block_size]; port.len()];
         }
+        self.set_aux_inputs();
+        self.events.clear();
+        self.block_events.clear();
+        self.in_place = self.latency_mode == LatencyMode::ZeroLatency;
 
-        let index_static = index;
-        for _ in 0..(samples - index_static) / self.block_size {
-            for channel in 0..channels {
-                self.temp
-                    .copy_from_slice(&slice[channel][index..index + self.block_size]);
-                slice[channel][index..index + self.block_size]
-                    .copy_from_slice(&self.buffer[channel][0..self.block_size]);
+        let max_latency = self.max_latency();
+        for delay in self.extra_channels.iter_mut() {
+            *delay = DelayLine::new(max_latency);
+        }
+        self.dry_delays = self.create_dry_delays();
+        self.modulation.allocate(block_size);
+    }
 
-                #[cfg(not(feature = "test"))]
-                self.params_block.from_params();
+    /// Set the [`LatencyMode`], [`LatencyMode::Buffered`] by default
+    pub fn with_latency_mode(mut self, latency_mode: LatencyMode) -> Self {
+        self.latency_mode = latency_mode;
+        self.in_place = latency_mode == LatencyMode::ZeroLatency;
+        self
+    }
 
-                match self.channel_processor[channel].process(
-                    &self.temp,
-                    &mut self.buffer[channel],
-                    &self.params_block,
-                ) {
-                    ProcessStatus::Error(e) => return ProcessStatus::Error(e),
-                    _ => {}
-                };
-            }
-            index += self.block_size;
-        }
+    /// Set the [`ChannelMatrix`] the first two channels are encoded with, [`ChannelMatrix::LeftRight`] by default
+    pub fn with_channel_matrix(mut self, channel_matrix: ChannelMatrix) -> Self {
+        self.channel_matrix = channel_matrix;
+        self
+    }
 
-        if index != samples {
-            self.overflow = samples - index;
+    /// Change the [`ChannelMatrix`] while processing, e.g. from a parameter.
+    ///
+    /// Takes effect from the next block. The processor keeps its state, so filters may ring out on the wrong channel.
+    pub fn set_channel_matrix(&mut self, channel_matrix: ChannelMatrix) {
+        self.channel_matrix = channel_matrix;
+    }
 
-            for channel in 0..channels {
-                let mut t;
-                for i in 0..self.overflow {
-                    t = slice[channel][i + index];
-                    slice[channel][i + index] = self.buffer[channel][i];
-                    self.buffer[channel][i] = t;
-                }
+    /// Set the [`ChannelPolicy`] for host channels beyond `channels`, [`ChannelPolicy::PassThrough`] by default.
+    ///
+    /// `max_channels` is the most channels the host can send. Buffers for them are allocated here, so no allocation happens
+    /// while processing. With [`ChannelPolicy::Clone`] the processor is recreated for `max_channels`, keeping the
+    /// [`ChannelRole`]s of the `channels` given to [`Self::new`], see [`MultiChannelProcessor::with_extra_channels`].
+    /// Channels beyond `max_channels` are always muted.
+    pub fn with_channel_policy(
+        mut self,
+        channel_policy: ChannelPolicy,
+        max_channels: usize,
+    ) -> Self {
+        self.channel_policy = channel_policy;
+        self.extra_channels.clear();
+
+        match channel_policy {
+            ChannelPolicy::PassThrough => {
+                self.extra_channels = (self.channels..max_channels)
+                    .map(|_| DelayLine::new(self.max_latency()))
+                    .collect();
             }
+            ChannelPolicy::Clone if max_channels > self.channels => {
+                self.processor = MCP::with_extra_channels(
+                    self.block_size,
+                    max_channels,
+                    self.channels,
+                    self.transport.sample_rate,
+                    self.params.clone(),
+                );
+                self.channels = max_channels;
+                self.allocate();
+            }
+            _ => {}
         }
+        self
+    }
 
-        ProcessStatus::Normal
+    /// Set the [`ErrorPolicy`] for blocks the processor fails on, [`ErrorPolicy::Propagate`] by default
+    pub fn with_error_policy(mut self, error_policy: ErrorPolicy) -> Self {
+        self.error_policy = error_policy;
+        self.dry_delays = self.create_dry_delays();
+        self
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::sync::Arc;
+    /// Bypass the processing when the [`BoolParam`] returned by `bypass` is set, crossfading over `fade_ms` milliseconds.
+    ///
+    /// The dry signal is delayed by the same amount as the processed one, so switching does not jump in time. The processor
+    /// keeps running while bypassed, so it is ready when switched back. Create the param with
+    /// [`BoolParam::make_bypass`], so the host bypass button drives it.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #[derive(Params)]
+    /// struct PluginParams {
+    ///     #[id = "bypass"]
+    ///     bypass: BoolParam,
+    /// }
+    ///
+    /// impl Default for PluginParams {
+    ///     fn default() -> Self {
+    ///         Self {
+    ///             bypass: BoolParam::new("Bypass", false).make_bypass(),
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let dsp = DspCoreProcessor::<Processor>::new(params, 64, 2, sample_rate).with_bypass(|p| &p.bypass, 10_f32);
+    /// ```
+    pub fn with_bypass(
+        mut self,
+        bypass: fn(&<MCP::ParamsBlock as ParamsBlock>::Params) -> &BoolParam,
+        fade_ms: f32,
+    ) -> Self {
+        self.bypass = Some(bypass);
+        self.bypass_fade_ms = fade_ms;
+        self.dry_delays = self.create_dry_delays();
+        self
+    }
 
-    use nih_plug::{buffer::Buffer, params::Params};
+    /// Set the [`Modulation`] applied to the [`ParamsBlock`] before every block is processed
+    pub fn with_modulation(mut self, mut modulation: Modulation<MCP::ParamsBlock>) -> Self {
+        modulation.allocate(self.block_size);
+        self.modulation = modulation;
+        self
+    }
 
-    use crate::{DspCoreProcessor, ParamsBlock, SingleChannelProcessor};
+    /// The [`Modulation`] of this processor, e.g. to change the depth of a route from a param
+    pub fn modulation_mut(&mut self) -> &mut Modulation<MCP::ParamsBlock> {
+        &mut self.modulation
+    }
 
-    #[derive(Params)]
-    struct ImplementsParams {}
-    struct Block {}
+    /// The processor being driven, e.g. a [`VoiceManager`] to send its terminated voices
+    pub fn processor(&self) -> &MCP {
+        &self.processor
+    }
+
+    /// Mutable access to the processor being driven
+    pub fn processor_mut(&mut self) -> &mut MCP {
+        &mut self.processor
+    }
+
+    /// The [`ErrorCounters`] of this processor.
+    ///
+    /// Clone it into your editor to show e.g. a warning when processing fails.
+    pub fn error_counters(&self) -> Arc<ErrorCounters> {
+        self.error_counters.clone()
+    }
+
+    /// The last error that happened while processing, if any
+    pub fn last_error(&self) -> Option<ProcessError> {
+        self.last_error
+    }
+
+    /// Set up auxiliary inputs, e.g. a sidechain, with the number of channels for each port.
+    ///
+    /// Pass `aux_input_ports` from your [`nih_plug::prelude::AudioIOLayout`]. The auxiliary inputs given to
+    /// [`Self::process_with_aux`] will be split into the same blocks, with the same delay, as the main buffer, and passed to
+    /// the processor through [`BlockContext`]. The processor gets the layout in [`SingleChannelProcessor::set_aux_inputs`].
+    /// Processing with fewer auxiliary inputs, e.g. with [`Self::process`], fails with [`ProcessError::MissingAuxInput`]
+    /// and processes nothing.
+    pub fn with_aux_inputs(mut self, aux_input_ports: &[NonZeroU32]) -> Self {
+        self.aux_temp = aux_input_ports
+            .iter()
+            .map(|channels| vec![vec![T::ZERO; self.block_size]; channels.get() as usize])
+            .collect();
+        self.set_aux_inputs();
+        self
+    }
+
+    // Tells the processor about the auxiliary inputs, see `SingleChannelProcessor::set_aux_inputs`
+    fn set_aux_inputs(&mut self) {
+        let aux_input_ports: Vec<usize> = self.aux_temp.iter().map(|port| port.len()).collect();
+        self.processor.set_aux_inputs(&aux_input_ports);
+    }
+
+    /// The [`LatencyMode`] the processor currently works in.
+    ///
+    /// This is [`LatencyMode::Buffered`] if [`LatencyMode::ZeroLatency`] had to fall back because of misaligned host buffers
+    pub fn latency_mode(&self) -> LatencyMode {
+        if self.in_place {
+            LatencyMode::ZeroLatency
+        } else {
+            LatencyMode::Buffered
+        }
+    }
+
+    /// Total latency in samples of the processing.
+    ///
+    /// This is the `block_size` samples of delay generated by splitting the buffer into blocks (none in
+    /// [`LatencyMode::ZeroLatency`]), plus the latency and lookahead declared by the processor. Report it to the host with
+    /// [`Self::init_latency`] and [`Self::update_latency`]
+    pub fn latency(&self) -> usize {
+        if self.in_place {
+            self.processor.latency()
+        } else {
+            self.block_size + self.processor.latency()
+        }
+    }
+
+    /// Number of samples the output keeps sounding after the input went silent.
+    ///
+    /// This is the [`Self::latency`], so the buffered samples get flushed, plus the [`SingleChannelProcessor::tail`] of the
+    /// processor. `usize::MAX` if the processor has an infinite tail.
+    pub fn tail(&self) -> usize {
+        self.latency().saturating_add(self.processor.tail())
+    }
+
+    /// Report the total latency to the host.
+    ///
+    /// Call this in [`nih_plug::prelude::Plugin::initialize`] after creating the processor.
+    pub fn init_latency<P: Plugin>(&mut self, context: &mut impl InitContext<P>) {
+        let latency = self.latency() as u32;
+        context.set_latency_samples(latency);
+        self.reported_latency = Some(latency);
+    }
+
+    /// Report the total latency to the host if it changed since it was last reported.
+    ///
+    /// Call this in [`nih_plug::prelude::Plugin::process`], so the host is notified whenever the latency of the processor
+    /// changes.
+    pub fn update_latency<P: Plugin>(&mut self, context: &mut impl ProcessContext<P>) {
+        let latency = self.latency() as u32;
+        if self.reported_latency != Some(latency) {
+            context.set_latency_samples(latency);
+            self.reported_latency = Some(latency);
+        }
+    }
+
+    /// Process the `buffer` using supplied processor type.
+    ///
+    /// The `buffer` will be split into blocks of given length, that will be processed by the processor, one span of time for
+    /// all channels at once. The struct stores overflow samples and will prevent any half processed blocks
+    ///
+    /// If the processor reports a [`SingleChannelProcessor::tail`], returns [`ProcessStatus::Tail`] with the samples left
+    /// until the output goes silent, see [`Self::tail`], so the host keeps processing silent buffers until the buffered audio
+    /// is flushed. Once it is, returns [`ProcessStatus::Normal`]. Processors with an infinite tail make it return
+    /// [`ProcessStatus::KeepAlive`]. Only the processed channels are checked for silence, and samples below -120 dB count
+    /// as silent.
+    pub fn process(&mut self, buffer: &mut Buffer) -> ProcessStatus {
+        self.process_inner(buffer.as_slice(), &[] as &[Buffer])
+    }
+
+    /// Process `channels`, holding one slice of samples per channel.
+    ///
+    /// Works like [`Self::process`], for when there is no nih_plug [`Buffer`], e.g. in offline tools or tests. All slices must
+    /// have the same length, otherwise nothing is processed and [`ProcessError::MismatchedLengths`] is returned.
+    pub fn process_slices(&mut self, channels: &mut [&mut [f32]]) -> ProcessStatus {
+        self.process_inner(channels, &[] as &[&[&[f32]]])
+    }
+
+    /// Process `channels` together with the auxiliary inputs in `aux_inputs`, holding one slice per channel for every port.
+    ///
+    /// Works like [`Self::process_with_aux`], see [`Self::process_slices`].
+    pub fn process_slices_with_aux(
+        &mut self,
+        channels: &mut [&mut [f32]],
+        aux_inputs: &[&[&[f32]]],
+    ) -> ProcessStatus {
+        self.process_inner(channels, aux_inputs)
+    }
+
+    /// Process interleaved `samples`, holding `channels` samples per frame.
+    ///
+    /// Works like [`Self::process`], for hosts or files that store frames of samples one after another. The length of
+    /// `samples` must be a multiple of `channels`, otherwise nothing is processed and [`ProcessError::MismatchedLengths`] is
+    /// returned.
+    pub fn process_interleaved(&mut self, samples: &mut [f32], channels: usize) -> ProcessStatus {
+        self.process_inner(&mut Interleaved { samples, channels }, &[] as &[&[&[f32]]])
+    }
+
+    /// Process the `buffer` together with the auxiliary inputs in `aux`.
+    ///
+    /// Works like [`Self::process`], while the auxiliary inputs set up in [`Self::with_aux_inputs`] are split into blocks
+    /// sample-aligned with the main blocks, and handed to the processor in its `process_with_context` function.
+    pub fn process_with_aux(
+        &mut self,
+        buffer: &mut Buffer,
+        aux: &mut AuxiliaryBuffers,
+    ) -> ProcessStatus {
+        self.process_inner(buffer.as_slice(), aux.inputs)
+    }
+
+    /// Process the `buffer` and `aux` inputs, together with the note events from `context`.
+    ///
+    /// Works like [`Self::process_with_aux`], while every note event from `context` is delivered to the processor with the
+    /// block containing its sample, see [`BlockContext::events`]. Events are rebased to the start of that block, and delayed
+    /// together with the audio they were sent with, so they land on the right sample. The same goes for the transport, see
+    /// [`BlockContext::transport`].
+    pub fn process_with_context<P: Plugin<SysExMessage = S>>(
+        &mut self,
+        buffer: &mut Buffer,
+        aux: &mut AuxiliaryBuffers,
+        context: &mut impl ProcessContext<P>,
+    ) -> ProcessStatus {
+        self.set_transport(context.transport().into());
+        while let Some(event) = context.next_event() {
+            self.queue_event(event);
+        }
+        self.process_inner(buffer.as_slice(), aux.inputs)
+    }
+
+    /// Queue a note event for the next processed buffer.
+    ///
+    /// Its [`NoteEvent::timing`] should be relative to the start of the next buffer passed to any of the process functions.
+    /// This is done for you by [`Self::process_with_context`]. Up to 1024 events can be pending, as storing more would allocate.
+    /// Events beyond that are dropped, and counted in [`ErrorCounters::dropped_events`].
+    pub fn queue_event(&mut self, event: NoteEvent<S>) {
+        if self.events.len() == self.events.capacity() {
+            self.error_counters.count_dropped_event();
+            return;
+        }
+        self.events.push(BlockEvent {
+            // Samples of the current block, collected before the next buffer
+            timing: self.overflow + event.timing() as usize,
+            event,
+        });
+    }
+
+    /// Set the transport at the start of the next processed buffer.
+    ///
+    /// This is done for you by [`Self::process_with_context`]. Processors receive it moved to the start of each block.
+    pub fn set_transport(&mut self, transport: TransportInfo) {
+        self.transport = transport;
+        // The current block started with the samples collected from previous buffers
+        self.transport.advance(-(self.overflow as i64));
+    }
+
+    fn process_inner<H: HostOutput + ?Sized, A: HostInput>(
+        &mut self,
+        host: &mut H,
+        aux_inputs: &[A],
+    ) -> ProcessStatus {
+        let samples = host.samples();
+        let channels = host.channels().min(self.channels);
+
+        if host.channels() == 0 {
+            self.last_error = Some(ProcessError::NoChannels);
+            return ProcessError::NoChannels.into();
+        }
+        if host.channels() > channels && self.channel_policy == ChannelPolicy::Error {
+            self.last_error = Some(ProcessError::TooManyChannels);
+            return ProcessError::TooManyChannels.into();
+        }
+        if !host.is_consistent()
+            || aux_inputs
+                .iter()
+                .any(|aux| aux.channels() > 0 && (!aux.is_consistent() || aux.samples() != samples))
+        {
+            self.last_error = Some(ProcessError::MismatchedLengths);
+            return ProcessError::MismatchedLengths.into();
+        }
+        if aux_inputs.len() < self.aux_temp.len() {
+            let error = ProcessError::MissingAuxInput(aux_inputs.len());
+            self.last_error = Some(error);
+            return error.into();
+        }
+
+        self.silent_samples = match host.last_sound(channels) {
+            Some(index) => samples - 1 - index,
+            None => self.silent_samples.saturating_add(samples),
+        };
+
+        if self.in_place
+            && !samples.is_multiple_of(self.block_size)
+            && !self.processor.accepts_variable_block_size()
+        {
+            self.in_place = false;
+            // The blocks still hold the last output processed in place, which must not be played again
+            for block in self.buffer.iter_mut() {
+                block.fill(T::ZERO);
+            }
+            self.overflow = 0;
+        }
+
+        let result = if self.in_place {
+            self.process_in_place(host, aux_inputs, channels, samples)
+        } else {
+            self.process_buffered(host, aux_inputs, channels, samples)
+        };
+        self.process_extra_channels(host, channels);
+
+        match result {
+            Ok(()) => self.tail_status(),
+            Err(error) => error.into(),
+        }
+    }
+
+    // Status telling the host how long the output keeps sounding, counted from the end of the last processed buffer. Only
+    // for processors reporting a tail, the others keep returning ProcessStatus::Normal
+    fn tail_status(&self) -> ProcessStatus {
+        if self.processor.tail() == 0 {
+            return ProcessStatus::Normal;
+        }
+        let tail = self.tail();
+        if tail == usize::MAX {
+            return ProcessStatus::KeepAlive;
+        }
+
+        match tail.saturating_sub(self.silent_samples) {
+            0 => ProcessStatus::Normal,
+            remaining => ProcessStatus::Tail(remaining as u32),
+        }
+    }
+
+    // Delays the host channels from `channels` on by the latency with ChannelPolicy::PassThrough, and mutes the rest
+    fn process_extra_channels<H: HostOutput + ?Sized>(&mut self, host: &mut H, channels: usize) {
+        let latency = self.latency();
+        // Without a max channel count there is nothing to delay with, the channels are left as they are
+        let untouched =
+            self.channel_policy == ChannelPolicy::PassThrough && self.extra_channels.is_empty();
+        for channel in channels..host.channels() {
+            match self.extra_channels.get_mut(channel - channels) {
+                Some(delay) => {
+                    // Only changes when falling back to LatencyMode::Buffered, which fits the initial capacity
+                    if delay.delay() != latency {
+                        delay.set_delay(latency);
+                    }
+                    host.map(channel, |sample| delay.next(sample));
+                }
+                None if untouched => {}
+                None => host.map(channel, |_| 0_f32),
+            }
+        }
+    }
+
+    // Latency in LatencyMode::Buffered, which is the most the processor can have
+    fn max_latency(&self) -> usize {
+        self.block_size + self.processor.latency()
+    }
+
+    // Processes the blocks directly from the host buffer, without any delay
+    fn process_in_place<H: HostOutput + ?Sized, A: HostInput>(
+        &mut self,
+        host: &mut H,
+        aux_inputs: &[A],
+        channels: usize,
+        samples: usize,
+    ) -> Result<(), ProcessError> {
+        let mut result = Ok(());
+        let mut index = 0;
+        while index < samples {
+            let len = self.block_size.min(samples - index);
+
+            for (channel, block) in self.temp.iter_mut().enumerate().take(channels) {
+                host.read(channel, index, &mut block[0..len]);
+            }
+            self.collect_aux(aux_inputs, 0..len, index);
+
+            if let Err(error) = self.process_block(channels, len) {
+                result = Err(error);
+            }
+
+            for (channel, block) in self.buffer.iter().enumerate().take(channels) {
+                host.write(channel, index, &block[0..len]);
+            }
+
+            index += len;
+        }
+
+        result
+    }
+
+    // Collects the blocks from the host buffer, delaying the signal by `block_size` samples
+    fn process_buffered<H: HostOutput + ?Sized, A: HostInput>(
+        &mut self,
+        host: &mut H,
+        aux_inputs: &[A],
+        channels: usize,
+        samples: usize,
+    ) -> Result<(), ProcessError> {
+        // `self.overflow` samples of the current block were already collected in `self.temp`, while the rest of the previously
+        // processed block in `self.buffer` still needs to be written out
+        let mut result = Ok(());
+        let mut index = 0;
+        while index < samples {
+            let len = (self.block_size - self.overflow).min(samples - index);
+            let range = self.overflow..self.overflow + len;
+
+            for channel in 0..channels {
+                host.read(channel, index, &mut self.temp[channel][range.clone()]);
+                host.write(channel, index, &self.buffer[channel][range.clone()]);
+            }
+            self.collect_aux(aux_inputs, range, index);
+
+            self.overflow += len;
+            index += len;
+
+            if self.overflow == self.block_size {
+                self.overflow = 0;
+
+                if let Err(error) = self.process_block(channels, self.block_size) {
+                    result = Err(error);
+                }
+            }
+        }
+
+        result
+    }
+
+    // Copies the auxiliary input samples starting at `index` into `range` of the auxiliary blocks
+    fn collect_aux<A: HostInput>(&mut self, aux_inputs: &[A], range: Range<usize>, index: usize) {
+        for (port, aux_input) in self.aux_temp.iter_mut().zip(aux_inputs) {
+            for (channel, block) in port.iter_mut().enumerate().take(aux_input.channels()) {
+                aux_input.read(channel, index, &mut block[range.clone()]);
+            }
+        }
+    }
+
+    // Processes the first `len` samples of `self.temp` into `self.buffer`
+    fn process_block(&mut self, channels: usize, len: usize) -> Result<(), ProcessError> {
+        let full = len == self.block_size;
+        if full {
+            #[cfg(not(feature = "test"))]
+            self.params_block.from_params();
+        } else {
+            // Shorten the blocks for processors accepting variable block sizes. This does not reallocate, the capacity stays
+            // the same
+            self.set_block_len(len);
+
+            #[cfg(not(feature = "test"))]
+            self.params_block.partial_from_params(len);
+        }
+        #[cfg(not(feature = "test"))]
+        self.modulation.apply(
+            &mut self.params_block,
+            &self.temp[0..channels],
+            len,
+            &self.transport,
+        );
+
+        self.split_events(len);
+        self.channel_matrix.encode(&mut self.temp[0..channels]);
+
+        let result = self.processor.try_process(
+            &self.temp[0..channels],
+            &mut self.buffer[0..channels],
+            &self.params_block,
+            &BlockContext::new(&self.aux_temp, &self.block_events, self.transport),
+        );
+        // An unstable processor is caught before its output reaches the host
+        let result = result.and_then(|()| {
+            let finite = self.buffer[0..channels]
+                .iter()
+                .flatten()
+                .all(|sample| sample.to_f64().is_finite());
+            if finite {
+                Ok(())
+            } else {
+                Err(ProcessError::NotFinite)
+            }
+        });
+        // The input is not read anymore, so it can be delayed in place to line up with the output
+        for (block, delay) in self.temp[0..channels]
+            .iter_mut()
+            .zip(self.dry_delays.iter_mut())
+        {
+            delay.process(block);
+        }
+        let result = self.recover(channels, result);
+        self.apply_bypass(channels, len);
+
+        self.channel_matrix.decode(&mut self.buffer[0..channels]);
+        if !full {
+            self.set_block_len(self.block_size);
+        }
+        self.transport.advance(len as i64);
+
+        result
+    }
+
+    // Replaces the output of a failed block according to the ErrorPolicy
+    fn recover(
+        &mut self,
+        channels: usize,
+        result: Result<(), ProcessError>,
+    ) -> Result<(), ProcessError> {
+        let Err(error) = result else {
+            return Ok(());
+        };
+        self.last_error = Some(error);
+        self.error_counters.count(self.error_policy);
+
+        for (output, dry) in self.buffer[0..channels].iter_mut().zip(self.temp.iter()) {
+            if self.error_policy == ErrorPolicy::Bypass {
+                output.copy_from_slice(dry);
+            } else {
+                output.fill(T::ZERO);
+            }
+        }
+
+        if self.error_policy == ErrorPolicy::Propagate {
+            Err(error)
+        } else {
+            Ok(())
+        }
+    }
+
+    // Crossfades the output of the block towards the delayed input in `self.temp`, following the bypass param
+    fn apply_bypass(&mut self, channels: usize, len: usize) {
+        let target = self.bypass_target();
+        if self.bypass_mix == target {
+            if target == 1_f32 {
+                for (output, dry) in self.buffer[0..channels].iter_mut().zip(self.temp.iter()) {
+                    output.copy_from_slice(dry);
+                }
+            }
+            return;
+        }
+
+        let step = 1000_f32 / (self.bypass_fade_ms * self.transport.sample_rate).max(1_f32);
+        let step = if target > self.bypass_mix {
+            step
+        } else {
+            -step
+        };
+        for (output, dry) in self.buffer[0..channels].iter_mut().zip(self.temp.iter()) {
+            let mut mix = self.bypass_mix;
+            for (output, &dry) in output.iter_mut().zip(dry.iter()) {
+                mix = (mix + step).clamp(0_f32, 1_f32);
+                let mix = T::from_f32(mix);
+                *output = *output * (T::ONE - mix) + dry * mix;
+            }
+        }
+        self.bypass_mix = (self.bypass_mix + step * len as f32).clamp(0_f32, 1_f32);
+    }
+
+    // Value `self.bypass_mix` is moving to
+    fn bypass_target(&self) -> f32 {
+        match self.bypass {
+            Some(bypass) if bypass(&self.params).value() => 1_f32,
+            _ => 0_f32,
+        }
+    }
+
+    // Dry signal delays for ErrorPolicy::Bypass and the bypass param, one per channel
+    fn create_dry_delays(&self) -> Vec<DelayLine<T>> {
+        if self.error_policy == ErrorPolicy::Bypass || self.bypass.is_some() {
+            (0..self.channels)
+                .map(|_| DelayLine::new(self.processor.latency()))
+                .collect()
+        } else {
+            Vec::new()
+        }
+    }
+
+    // Moves the pending events belonging to the next `len` samples into `self.block_events`, and rebases the rest
+    fn split_events(&mut self, len: usize) {
+        self.block_events.clear();
+        self.block_events.extend(
+            self.events
+                .iter()
+                .filter(|event| event.timing < len)
+                .cloned(),
+        );
+
+        self.events.retain(|event| event.timing >= len);
+        for event in self.events.iter_mut() {
+            event.timing -= len;
+        }
+    }
+
+    // Truncates or restores the length of all blocks, without reallocating
+    fn set_block_len(&mut self, len: usize) {
+        for block in self
+            .temp
+            .iter_mut()
+            .chain(self.buffer.iter_mut())
+            .chain(self.aux_temp.iter_mut().flatten())
+        {
+            block.resize(len, T::ZERO);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    #[cfg(not(feature = "test"))]
+    use nih_plug::params::smoothing::{Smoother, SmoothingStyle};
+    use nih_plug::params::{BoolParam, FloatParam, IntParam};
+    use nih_plug::prelude::{AuxiliaryBuffers, NoteEvent, ProcessStatus, SysExMessage};
+    use nih_plug::prelude::{FloatRange, IntRange};
+    use nih_plug::{buffer::Buffer, params::Params};
+    use std::num::NonZeroU32;
+    use std::sync::OnceLock;
+
+    use crate::delay::DelayLine;
+    use crate::{
+        BlockContext, ChannelInfo, ChannelMatrix, ChannelPolicy, ChannelRole, DspCoreProcessor,
+        ErrorPolicy, LatencyMode, LinkedDspCoreProcessor, MultiChannelProcessor, ParamsBlock,
+        ProcessError, SingleChannelProcessor, TransportInfo,
+    };
+    #[cfg(not(feature = "test"))]
+    use crate::{Modulation, Rate, StepSequencer};
+
+    #[derive(Params)]
+    struct ImplementsParams {}
+    struct Block {}
     impl ParamsBlock for Block {
         type Params = ImplementsParams;
         fn new(_params: std::sync::Arc<Self::Params>, _block_size: usize) -> Self {
@@ -476,7 +1784,12 @@ mod tests {
     }
     impl SingleChannelProcessor for Single {
         type ParamsBlock = Block;
-        fn new(block_size: usize, _sample_rate: f32, _params: Arc<ImplementsParams>) -> Self {
+        fn new(
+            block_size: usize,
+            _channel: ChannelInfo,
+            _sample_rate: f32,
+            _params: Arc<ImplementsParams>,
+        ) -> Self {
             Self { block_size }
         }
         fn process(
@@ -495,6 +1808,39 @@ mod tests {
             nih_plug::plugin::ProcessStatus::Normal
         }
     }
+    struct Swap {
+        block_size: usize,
+        channels: usize,
+    }
+    impl MultiChannelProcessor for Swap {
+        type ParamsBlock = Block;
+        fn new(
+            block_size: usize,
+            channels: usize,
+            _sample_rate: f32,
+            _params: Arc<ImplementsParams>,
+        ) -> Self {
+            Self {
+                block_size,
+                channels,
+            }
+        }
+        fn process(
+            &mut self,
+            blocks: &[Vec<f32>],
+            outputs: &mut [Vec<f32>],
+            _params_block: &Self::ParamsBlock,
+        ) -> nih_plug::prelude::ProcessStatus {
+            assert_eq!(self.channels, blocks.len());
+            assert_eq!(self.channels, outputs.len());
+
+            for channel in 0..self.channels {
+                assert_eq!(self.block_size, blocks[channel].len());
+                outputs[channel].copy_from_slice(&blocks[self.channels - 1 - channel]);
+            }
+            nih_plug::plugin::ProcessStatus::Normal
+        }
+    }
 
     // Makes sure the overflow prevention system is working as intended, every sample is processed once and in blocks of block_size
 
@@ -542,4 +1888,1434 @@ mod tests {
         assert_eq!(expected, buffer.as_slice_immutable()[1]);
         assert_eq!(expected, buffer.as_slice_immutable()[2]);
     }
+
+    struct Delayed {}
+    impl SingleChannelProcessor for Delayed {
+        type ParamsBlock = Block;
+        fn new(
+            _block_size: usize,
+            _channel: ChannelInfo,
+            _sample_rate: f32,
+            _params: Arc<ImplementsParams>,
+        ) -> Self {
+            Self {}
+        }
+        fn process(
+            &mut self,
+            block: &[f32],
+            output: &mut [f32],
+            _params_block: &Self::ParamsBlock,
+        ) -> nih_plug::prelude::ProcessStatus {
+            output.copy_from_slice(block);
+            nih_plug::plugin::ProcessStatus::Normal
+        }
+        fn latency(&self) -> usize {
+            5
+        }
+    }
+
+    struct Variable {
+        block_size: usize,
+    }
+    impl SingleChannelProcessor for Variable {
+        type ParamsBlock = Block;
+        fn new(
+            block_size: usize,
+            _channel: ChannelInfo,
+            _sample_rate: f32,
+            _params: Arc<ImplementsParams>,
+        ) -> Self {
+            Self { block_size }
+        }
+        fn process(
+            &mut self,
+            block: &[f32],
+            output: &mut [f32],
+            _params_block: &Self::ParamsBlock,
+        ) -> nih_plug::prelude::ProcessStatus {
+            assert!(block.len() <= self.block_size);
+            assert_eq!(block.len(), output.len());
+
+            for i in 0..output.len() {
+                output[i] = block[i] + 1_f32;
+            }
+            nih_plug::plugin::ProcessStatus::Normal
+        }
+        fn accepts_variable_block_size(&self) -> bool {
+            true
+        }
+    }
+
+    fn process_ramp<MCP: MultiChannelProcessor>(
+        proc: &mut LinkedDspCoreProcessor<MCP>,
+        buffer_sizes: &[usize],
+    ) -> Vec<f32> {
+        let mut output: Vec<f32> = (0..buffer_sizes.iter().sum()).map(|i| i as f32).collect();
+
+        let mut start = 0;
+        for &len in buffer_sizes {
+            let mut buffer = Buffer::default();
+            unsafe {
+                buffer.set_slices(len, |slices| {
+                    *slices = vec![&mut output[start..start + len]];
+                })
+            }
+            proc.process(&mut buffer);
+            start += len;
+        }
+        output
+    }
+
+    #[test]
+    fn test_zero_latency() {
+        let expected: Vec<f32> = (0..256).map(|i| i as f32 + 1_f32).collect();
+
+        // Aligned host buffers are processed in place
+        let mut proc: DspCoreProcessor<Single> =
+            DspCoreProcessor::new(Arc::new(ImplementsParams {}), 32, 1, 0_f32)
+                .with_latency_mode(LatencyMode::ZeroLatency);
+        assert_eq!(expected, process_ramp(&mut proc, &[64, 128, 64]));
+        assert_eq!(0, proc.latency());
+        assert_eq!(LatencyMode::ZeroLatency, proc.latency_mode());
+
+        // Variable block size processors never need to delay
+        let mut proc: DspCoreProcessor<Variable> =
+            DspCoreProcessor::new(Arc::new(ImplementsParams {}), 32, 1, 0_f32)
+                .with_latency_mode(LatencyMode::ZeroLatency);
+        assert_eq!(expected, process_ramp(&mut proc, &[7, 100, 33, 116]));
+        assert_eq!(0, proc.latency());
+
+        // Misaligned buffers make fixed block size processors fall back to buffering
+        let mut proc: DspCoreProcessor<Single> =
+            DspCoreProcessor::new(Arc::new(ImplementsParams {}), 32, 1, 0_f32)
+                .with_latency_mode(LatencyMode::ZeroLatency);
+        let output = process_ramp(&mut proc, &[64, 20]);
+        assert_eq!(32, proc.latency());
+        assert_eq!(LatencyMode::Buffered, proc.latency_mode());
+        // Starts from silence rather than replaying the last block processed in place
+        assert_eq!(vec![0_f32; 20], output[64..84]);
+
+        // Resetting tries processing in place again
+        proc.reset();
+        assert_eq!(LatencyMode::ZeroLatency, proc.latency_mode());
+        assert_eq!(expected[0..64], process_ramp(&mut proc, &[64]));
+    }
+
+    #[test]
+    fn test_latency() {
+        let proc: DspCoreProcessor<Single> =
+            DspCoreProcessor::new(Arc::new(ImplementsParams {}), 23, 3, 0_f32);
+        assert_eq!(23, proc.latency());
+
+        let proc: DspCoreProcessor<Delayed> =
+            DspCoreProcessor::new(Arc::new(ImplementsParams {}), 23, 3, 0_f32);
+        assert_eq!(28, proc.latency());
+    }
+
+    // Outputs the sample 4 samples ahead of the block
+    struct Ahead {}
+    impl SingleChannelProcessor for Ahead {
+        type ParamsBlock = Block;
+        fn new(
+            _block_size: usize,
+            _channel: ChannelInfo,
+            _sample_rate: f32,
+            _params: Arc<ImplementsParams>,
+        ) -> Self {
+            Self {}
+        }
+        fn process(
+            &mut self,
+            _block: &[f32],
+            _output: &mut [f32],
+            _params_block: &Self::ParamsBlock,
+        ) -> ProcessStatus {
+            ProcessStatus::Error("No lookahead")
+        }
+        fn process_with_context<S: SysExMessage>(
+            &mut self,
+            block: &[f32],
+            output: &mut [f32],
+            _params_block: &Self::ParamsBlock,
+            context: &BlockContext<f32, S>,
+        ) -> ProcessStatus {
+            assert_eq!(4, context.lookahead().len());
+            for (output, sample) in output
+                .iter_mut()
+                .zip(block.iter().chain(context.lookahead()).skip(4))
+            {
+                *output = *sample;
+            }
+            ProcessStatus::Normal
+        }
+        fn lookahead(&self) -> usize {
+            4
+        }
+    }
+
+    #[test]
+    fn test_lookahead() {
+        let mut proc: DspCoreProcessor<Ahead> =
+            DspCoreProcessor::new(Arc::new(ImplementsParams {}), 8, 1, 0_f32);
+        assert_eq!(12, proc.latency());
+
+        // Seeing the lookahead cancels the delay it adds
+        let expected: Vec<f32> = (0..64).map(|i| (i as f32 - 8_f32).max(0_f32)).collect();
+        assert_eq!(expected, process_ramp(&mut proc, &[20, 30, 14]));
+    }
+
+    // Adds the index of its channel to the signal
+    struct Indexed {
+        channel: ChannelInfo,
+    }
+    impl SingleChannelProcessor for Indexed {
+        type ParamsBlock = Block;
+        fn new(
+            _block_size: usize,
+            channel: ChannelInfo,
+            _sample_rate: f32,
+            _params: Arc<ImplementsParams>,
+        ) -> Self {
+            Self { channel }
+        }
+        fn process(
+            &mut self,
+            block: &[f32],
+            output: &mut [f32],
+            _params_block: &Self::ParamsBlock,
+        ) -> nih_plug::prelude::ProcessStatus {
+            for (output, sample) in output.iter_mut().zip(block) {
+                *output = sample + self.channel.index as f32;
+            }
+            nih_plug::plugin::ProcessStatus::Normal
+        }
+    }
+
+    // Processes a ramp on 3 channels with a processor configured for 2
+    fn process_extra_channel(channel_policy: ChannelPolicy) -> (Vec<Vec<f32>>, ProcessStatus) {
+        let mut proc: DspCoreProcessor<Indexed> =
+            DspCoreProcessor::new(Arc::new(ImplementsParams {}), 8, 2, 0_f32)
+                .with_channel_policy(channel_policy, 3);
+
+        let mut output: Vec<Vec<f32>> = vec![(0..64).map(|i| i as f32).collect(); 3];
+        let mut status = ProcessStatus::Normal;
+        for start in (0..64).step_by(16) {
+            let mut buffer = Buffer::default();
+            unsafe {
+                buffer.set_slices(16, |slices| {
+                    *slices = output
+                        .iter_mut()
+                        .map(|channel| &mut channel[start..start + 16])
+                        .collect();
+                })
+            }
+            status = proc.process(&mut buffer);
+        }
+        (output, status)
+    }
+
+    #[test]
+    fn test_channel_policy() {
+        let delayed = |offset: f32| -> Vec<f32> {
+            (0..64)
+                .map(|i| {
+                    if i < 8 {
+                        0_f32
+                    } else {
+                        (i - 8) as f32 + offset
+                    }
+                })
+                .collect()
+        };
+
+        let (output, _) = process_extra_channel(ChannelPolicy::Mute);
+        assert_eq!(delayed(1_f32)[8..], output[1][8..]);
+        assert_eq!(vec![0_f32; 64], output[2]);
+
+        // Stays aligned with the processed channels
+        let (output, _) = process_extra_channel(ChannelPolicy::PassThrough);
+        assert_eq!(delayed(0_f32), output[2]);
+
+        let (output, _) = process_extra_channel(ChannelPolicy::Clone);
+        assert_eq!(delayed(2_f32)[8..], output[2][8..]);
+
+        let (_, status) = process_extra_channel(ChannelPolicy::Error);
+        assert!(matches!(status, ProcessStatus::Error(_)));
+
+        // Without a max channel count, extra channels are left untouched
+        let mut proc: DspCoreProcessor<Indexed> =
+            DspCoreProcessor::new(Arc::new(ImplementsParams {}), 8, 2, 0_f32);
+        let mut output: Vec<Vec<f32>> = vec![(0..16).map(|i| i as f32).collect(); 3];
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(16, |slices| {
+                *slices = output
+                    .iter_mut()
+                    .map(|channel| channel.as_mut_slice())
+                    .collect();
+            })
+        }
+        proc.process(&mut buffer);
+        assert_eq!((0..16).map(|i| i as f32).collect::<Vec<f32>>(), output[2]);
+
+        // Cloned processors keep the roles of the main layout
+        let proc: DspCoreProcessor<Indexed> =
+            DspCoreProcessor::new(Arc::new(ImplementsParams {}), 8, 2, 0_f32)
+                .with_channel_policy(ChannelPolicy::Clone, 4);
+        let roles: Vec<ChannelRole> = proc
+            .processor()
+            .channel_processor
+            .iter()
+            .map(|processor| processor.channel.role)
+            .collect();
+        assert_eq!(
+            vec![
+                ChannelRole::Left,
+                ChannelRole::Right,
+                ChannelRole::Other,
+                ChannelRole::Other
+            ],
+            roles
+        );
+    }
+
+    // Makes sure slices and interleaved samples are processed exactly like a buffer
+
+    #[test]
+    fn test_slices() {
+        let (expected, _) = process_extra_channel(ChannelPolicy::PassThrough);
+        let new_proc = || -> DspCoreProcessor<Indexed> {
+            DspCoreProcessor::new(Arc::new(ImplementsParams {}), 8, 2, 0_f32)
+                .with_channel_policy(ChannelPolicy::PassThrough, 3)
+        };
+
+        let mut proc = new_proc();
+        let mut output: Vec<Vec<f32>> = vec![(0..64).map(|i| i as f32).collect(); 3];
+        for start in (0..64).step_by(16) {
+            let mut slices: Vec<&mut [f32]> = output
+                .iter_mut()
+                .map(|channel| &mut channel[start..start + 16])
+                .collect();
+            proc.process_slices(&mut slices);
+        }
+        assert_eq!(expected, output);
+
+        let mut proc = new_proc();
+        let mut interleaved: Vec<f32> = (0..64 * 3).map(|i| (i / 3) as f32).collect();
+        for block in interleaved.chunks_mut(16 * 3) {
+            proc.process_interleaved(block, 3);
+        }
+        for (channel, expected) in expected.iter().enumerate() {
+            let output: Vec<f32> = interleaved
+                .iter()
+                .skip(channel)
+                .step_by(3)
+                .copied()
+                .collect();
+            assert_eq!(*expected, output);
+        }
+
+        // Lengths that don't line up are rejected instead of read out of bounds
+        let mut proc = new_proc();
+        let (mut left, mut right) = (vec![1_f32; 16], vec![1_f32; 15]);
+        let status = proc.process_slices(&mut [&mut left, &mut right]);
+        assert!(matches!(status, ProcessStatus::Error(_)));
+        assert_eq!(Some(ProcessError::MismatchedLengths), proc.last_error());
+
+        let mut interleaved = vec![1_f32; 7];
+        let status = proc.process_interleaved(&mut interleaved, 2);
+        assert!(matches!(status, ProcessStatus::Error(_)));
+        assert_eq!(vec![1_f32; 7], interleaved);
+    }
+
+    // Adds 1 and delays by 2 samples, failing on the second block
+    struct Failing {
+        delay: DelayLine<f32>,
+        blocks: usize,
+    }
+    impl SingleChannelProcessor for Failing {
+        type ParamsBlock = Block;
+        fn new(
+            _block_size: usize,
+            _channel: ChannelInfo,
+            _sample_rate: f32,
+            _params: Arc<ImplementsParams>,
+        ) -> Self {
+            Self {
+                delay: DelayLine::new(2),
+                blocks: 0,
+            }
+        }
+        fn process(
+            &mut self,
+            block: &[f32],
+            output: &mut [f32],
+            _params_block: &Self::ParamsBlock,
+        ) -> nih_plug::prelude::ProcessStatus {
+            output.copy_from_slice(block);
+            self.delay.process(output);
+            for sample in output.iter_mut() {
+                *sample += 1_f32;
+            }
+
+            self.blocks += 1;
+            if self.blocks == 2 {
+                nih_plug::plugin::ProcessStatus::Error("Failed")
+            } else {
+                nih_plug::plugin::ProcessStatus::Normal
+            }
+        }
+        fn latency(&self) -> usize {
+            2
+        }
+    }
+
+    // Makes sure a failed block does not stop the rest of the buffer from being processed
+
+    #[test]
+    fn test_error_policy() {
+        for error_policy in [
+            ErrorPolicy::Propagate,
+            ErrorPolicy::Silence,
+            ErrorPolicy::Bypass,
+        ] {
+            let mut proc: DspCoreProcessor<Failing> =
+                DspCoreProcessor::new(Arc::new(ImplementsParams {}), 8, 1, 0_f32)
+                    .with_error_policy(error_policy);
+            let counters = proc.error_counters();
+
+            let mut output: Vec<f32> = (0..32).map(|i| i as f32).collect();
+            let mut buffer = Buffer::default();
+            unsafe {
+                buffer.set_slices(32, |slices| {
+                    *slices = vec![&mut output];
+                })
+            }
+            let status = proc.process(&mut buffer);
+
+            // The second block is processed into samples 16..24, delayed by 10 samples in total
+            let failed = match error_policy {
+                ErrorPolicy::Bypass => (6..14).map(|i| i as f32).collect(),
+                _ => vec![0_f32; 8],
+            };
+            let processed: Vec<f32> = (0..8).map(|i| i as f32 + 15_f32).collect();
+            assert_eq!(failed, output[16..24]);
+            assert_eq!(processed, output[24..32]);
+
+            assert_eq!(1, counters.errors());
+            assert_eq!(Some(ProcessError::Processor("Failed")), proc.last_error());
+            if error_policy == ErrorPolicy::Propagate {
+                assert!(matches!(status, ProcessStatus::Error("Failed")));
+                assert_eq!(0, counters.recovered());
+            } else {
+                assert!(matches!(status, ProcessStatus::Normal));
+                assert_eq!(1, counters.recovered());
+            }
+        }
+    }
+
+    // Passes the signal through, failing the second block with a typed error on every channel
+    struct Typed {
+        channel: usize,
+        blocks: usize,
+    }
+    impl SingleChannelProcessor for Typed {
+        type ParamsBlock = Block;
+        fn new(
+            _block_size: usize,
+            channel: ChannelInfo,
+            _sample_rate: f32,
+            _params: Arc<ImplementsParams>,
+        ) -> Self {
+            Self {
+                channel: channel.index,
+                blocks: 0,
+            }
+        }
+        fn process(
+            &mut self,
+            block: &[f32],
+            output: &mut [f32],
+            params_block: &Self::ParamsBlock,
+        ) -> nih_plug::prelude::ProcessStatus {
+            let context = BlockContext::<f32, ()>::default();
+            self.try_process(block, output, params_block, &context)
+                .map_or_else(ProcessStatus::from, |_| ProcessStatus::Normal)
+        }
+        fn try_process<S: SysExMessage>(
+            &mut self,
+            block: &[f32],
+            output: &mut [f32],
+            _params_block: &Self::ParamsBlock,
+            _context: &BlockContext<f32, S>,
+        ) -> Result<(), ProcessError> {
+            output.copy_from_slice(block);
+            self.blocks += 1;
+            match (self.blocks, self.channel) {
+                (2, 0) => Err(ProcessError::InvalidParam("Gain")),
+                (2, _) => Err(ProcessError::NotFinite),
+                _ => Ok(()),
+            }
+        }
+    }
+
+    // Makes sure typed errors reach the host, and a failing channel does not stop the others from being processed
+
+    #[test]
+    fn test_typed_errors() {
+        let mut proc: DspCoreProcessor<Typed> =
+            DspCoreProcessor::new(Arc::new(ImplementsParams {}), 8, 2, 0_f32);
+
+        let mut output = vec![vec![1_f32; 32]; 2];
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(32, |slices| {
+                *slices = output
+                    .iter_mut()
+                    .map(|channel| channel.as_mut_slice())
+                    .collect();
+            })
+        }
+        let status = proc.process(&mut buffer);
+
+        assert!(matches!(
+            status,
+            ProcessStatus::Error("Invalid parameter value")
+        ));
+        assert_eq!(Some(ProcessError::InvalidParam("Gain")), proc.last_error());
+        let blocks: Vec<usize> = proc
+            .processor()
+            .channel_processor
+            .iter()
+            .map(|processor| processor.blocks)
+            .collect();
+        assert_eq!(vec![4, 4], blocks);
+    }
+
+    // Outputs NaN from the second block on
+    struct Unstable {
+        blocks: usize,
+    }
+    impl SingleChannelProcessor for Unstable {
+        type ParamsBlock = Block;
+        fn new(
+            _block_size: usize,
+            _channel: ChannelInfo,
+            _sample_rate: f32,
+            _params: Arc<ImplementsParams>,
+        ) -> Self {
+            Self { blocks: 0 }
+        }
+        fn process(
+            &mut self,
+            block: &[f32],
+            output: &mut [f32],
+            _params_block: &Self::ParamsBlock,
+        ) -> nih_plug::prelude::ProcessStatus {
+            self.blocks += 1;
+            if self.blocks < 2 {
+                output.copy_from_slice(block);
+            } else {
+                output.fill(f32::NAN);
+            }
+            ProcessStatus::Normal
+        }
+    }
+
+    // Makes sure non finite output is reported and replaced, and missing auxiliary inputs are reported
+
+    #[test]
+    fn test_checked_errors() {
+        let mut proc: DspCoreProcessor<Unstable> =
+            DspCoreProcessor::new(Arc::new(ImplementsParams {}), 8, 1, 0_f32)
+                .with_latency_mode(LatencyMode::ZeroLatency)
+                .with_error_policy(ErrorPolicy::Silence);
+        let mut output = [1_f32; 16];
+        assert!(matches!(
+            proc.process_slices(&mut [&mut output]),
+            ProcessStatus::Normal
+        ));
+        assert_eq!(Some(ProcessError::NotFinite), proc.last_error());
+        assert_eq!([1_f32; 8], output[0..8]);
+        assert_eq!([0_f32; 8], output[8..16]);
+
+        let mut proc: DspCoreProcessor<Unstable> =
+            DspCoreProcessor::new(Arc::new(ImplementsParams {}), 8, 1, 0_f32)
+                .with_aux_inputs(&[NonZeroU32::new(1).unwrap()]);
+        assert!(matches!(
+            proc.process_slices(&mut [&mut output]),
+            ProcessStatus::Error("Auxiliary input not connected")
+        ));
+        assert_eq!(Some(ProcessError::MissingAuxInput(0)), proc.last_error());
+        assert_eq!(0, proc.processor().channel_processor[0].blocks);
+    }
+
+    fn bypassed(_params: &ImplementsParams) -> &BoolParam {
+        static BYPASSED: OnceLock<BoolParam> = OnceLock::new();
+        BYPASSED.get_or_init(|| BoolParam::new("Bypass", true).make_bypass())
+    }
+
+    // Makes sure bypassing crossfades to the input, delayed like the processed signal
+
+    #[test]
+    fn test_bypass() {
+        let mut proc: DspCoreProcessor<Single> =
+            DspCoreProcessor::new(Arc::new(ImplementsParams {}), 8, 1, 8000_f32)
+                .with_bypass(bypassed, 1_f32);
+
+        let mut output = vec![1_f32; 32];
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(32, |slices| {
+                *slices = vec![&mut output];
+            })
+        }
+        proc.process(&mut buffer);
+
+        // Fades from the processed signal over 8 samples
+        let fade: Vec<f32> = (1..=8).map(|i| 2_f32 - i as f32 / 8_f32).collect();
+        assert_eq!(fade, output[8..16]);
+        assert_eq!(vec![1_f32; 16], output[16..32]);
+
+        // Resetting skips the fade
+        proc.reset();
+        let mut output = vec![1_f32; 32];
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(32, |slices| {
+                *slices = vec![&mut output];
+            })
+        }
+        proc.process(&mut buffer);
+        assert_eq!(vec![1_f32; 24], output[8..32]);
+    }
+
+    // Passes the signal through, reporting a tail of `TAIL` samples
+    struct Ringing<const TAIL: usize> {}
+    impl<const TAIL: usize> SingleChannelProcessor for Ringing<TAIL> {
+        type ParamsBlock = Block;
+        fn new(
+            _block_size: usize,
+            _channel: ChannelInfo,
+            _sample_rate: f32,
+            _params: Arc<ImplementsParams>,
+        ) -> Self {
+            Self {}
+        }
+        fn process(
+            &mut self,
+            block: &[f32],
+            output: &mut [f32],
+            _params_block: &Self::ParamsBlock,
+        ) -> nih_plug::prelude::ProcessStatus {
+            output.copy_from_slice(block);
+            nih_plug::plugin::ProcessStatus::Normal
+        }
+        fn tail(&self) -> usize {
+            TAIL
+        }
+    }
+
+    fn process_constant<SCP: SingleChannelProcessor<ParamsBlock = Block>>(
+        proc: &mut DspCoreProcessor<SCP>,
+        value: f32,
+    ) -> ProcessStatus {
+        let mut output = vec![value; 16];
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(16, |slices| {
+                *slices = vec![&mut output];
+            })
+        }
+        proc.process(&mut buffer)
+    }
+
+    #[test]
+    fn test_tail() {
+        let mut proc: DspCoreProcessor<Ringing<20>> =
+            DspCoreProcessor::new(Arc::new(ImplementsParams {}), 8, 1, 0_f32);
+        assert_eq!(28, proc.tail());
+
+        assert!(matches!(
+            process_constant(&mut proc, 1_f32),
+            ProcessStatus::Tail(28)
+        ));
+        // Counts down while the input is silent, until the buffered samples and the tail are flushed
+        assert!(matches!(
+            process_constant(&mut proc, 0_f32),
+            ProcessStatus::Tail(12)
+        ));
+        assert!(matches!(
+            process_constant(&mut proc, 0_f32),
+            ProcessStatus::Normal
+        ));
+        assert!(matches!(
+            process_constant(&mut proc, 1_f32),
+            ProcessStatus::Tail(28)
+        ));
+
+        // Nearly silent input counts as silence
+        assert!(matches!(
+            process_constant(&mut proc, 1e-7_f32),
+            ProcessStatus::Tail(12)
+        ));
+
+        let mut proc: DspCoreProcessor<Ringing<{ usize::MAX }>> =
+            DspCoreProcessor::new(Arc::new(ImplementsParams {}), 8, 1, 0_f32);
+        assert!(matches!(
+            process_constant(&mut proc, 0_f32),
+            ProcessStatus::KeepAlive
+        ));
+
+        // Processors without a tail are not kept alive for the buffered samples
+        let mut proc: DspCoreProcessor<Ringing<0>> =
+            DspCoreProcessor::new(Arc::new(ImplementsParams {}), 8, 1, 0_f32);
+        assert!(matches!(
+            process_constant(&mut proc, 1_f32),
+            ProcessStatus::Normal
+        ));
+
+        // Extra host channels are not checked for silence
+        let mut proc: DspCoreProcessor<Ringing<20>> =
+            DspCoreProcessor::new(Arc::new(ImplementsParams {}), 8, 1, 0_f32);
+        let mut output = [vec![0_f32; 16], vec![1_f32; 16]];
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(16, |slices| {
+                *slices = output
+                    .iter_mut()
+                    .map(|channel| channel.as_mut_slice())
+                    .collect();
+            })
+        }
+        assert!(matches!(proc.process(&mut buffer), ProcessStatus::Normal));
+    }
+
+    // The modulation is added to the values read by `from_params`, which is not called with the `test` feature
+    #[cfg(not(feature = "test"))]
+    struct GainBlock {
+        gain: Vec<f32>,
+    }
+    #[cfg(not(feature = "test"))]
+    impl ParamsBlock for GainBlock {
+        type Params = ImplementsParams;
+        fn new(_params: Arc<Self::Params>, block_size: usize) -> Self {
+            Self {
+                gain: vec![1_f32; block_size],
+            }
+        }
+        fn from_params(&mut self) {
+            self.gain.fill(1_f32);
+        }
+    }
+
+    // Outputs the gain it gets
+    #[cfg(not(feature = "test"))]
+    struct Gain {}
+    #[cfg(not(feature = "test"))]
+    impl SingleChannelProcessor for Gain {
+        type ParamsBlock = GainBlock;
+        fn new(
+            _block_size: usize,
+            _channel: ChannelInfo,
+            _sample_rate: f32,
+            _params: Arc<ImplementsParams>,
+        ) -> Self {
+            Self {}
+        }
+        fn process(
+            &mut self,
+            _block: &[f32],
+            output: &mut [f32],
+            params_block: &Self::ParamsBlock,
+        ) -> nih_plug::prelude::ProcessStatus {
+            output.copy_from_slice(&params_block.gain);
+            nih_plug::plugin::ProcessStatus::Normal
+        }
+    }
+
+    // Makes sure the modulation is added to the params block on every block, from the values read from the params
+
+    #[cfg(not(feature = "test"))]
+    #[test]
+    fn test_modulation() {
+        let mut proc: DspCoreProcessor<Gain> =
+            DspCoreProcessor::new(Arc::new(ImplementsParams {}), 8, 1, 16_f32).with_modulation(
+                Modulation::new()
+                    .with_source(StepSequencer::new(vec![1_f32, -1_f32], Rate::Hz(1_f32)))
+                    .with_route(
+                        0,
+                        |params_block: &mut GainBlock| &mut params_block.gain,
+                        0.5_f32,
+                    ),
+            );
+
+        let mut output = [0_f32; 40];
+        for block in output.chunks_mut(10) {
+            proc.process_slices(&mut [block]);
+        }
+        assert_eq!([1.5_f32; 16], output[8..24]);
+        assert_eq!([0.5_f32; 16], output[24..40]);
+
+        proc.modulation_mut().set_depth(0, 0_f32);
+        proc.process_slices(&mut [&mut output[0..16]]);
+        assert_eq!([1_f32; 8], output[8..16]);
+    }
+
+    // Makes sure linked processors see every channel of the same span of time, regardless of host buffer sizes
+
+    #[test]
+    fn test_linked_processing() {
+        let mut proc: LinkedDspCoreProcessor<Swap> =
+            LinkedDspCoreProcessor::new(Arc::new(ImplementsParams {}), 16, 2, 0_f32);
+
+        let input: Vec<Vec<f32>> = vec![
+            (0..200).map(|i| i as f32).collect(),
+            (0..200).map(|i| -(i as f32)).collect(),
+        ];
+        let mut output = vec![vec![0_f32; 200]; 2];
+
+        let mut start = 0;
+        for len in [7, 40, 3, 64, 86] {
+            let (left, right) = output.split_at_mut(1);
+            let mut buffer = Buffer::default();
+            unsafe {
+                buffer.set_slices(len, |slices| {
+                    *slices = vec![
+                        &mut left[0][start..start + len],
+                        &mut right[0][start..start + len],
+                    ];
+                })
+            }
+            buffer.as_slice()[0].copy_from_slice(&input[0][start..start + len]);
+            buffer.as_slice()[1].copy_from_slice(&input[1][start..start + len]);
+
+            proc.process(&mut buffer);
+            start += len;
+        }
+
+        // 16 samples of delay, then channels are swapped
+        for i in 0..200 {
+            let expected = if i < 16 {
+                [0_f32, 0_f32]
+            } else {
+                [input[1][i - 16], input[0][i - 16]]
+            };
+            assert_eq!(expected, [output[0][i], output[1][i]]);
+        }
+    }
+
+    // Makes sure the processor sees encoded channels, and the output gets decoded
+
+    #[test]
+    fn test_channel_matrix() {
+        let mut left: Vec<f32> = (0..64).map(|i| i as f32).collect();
+        let mut right: Vec<f32> = (0..64).map(|i| 2_f32 * i as f32).collect();
+
+        let mut proc: LinkedDspCoreProcessor<Swap> =
+            LinkedDspCoreProcessor::new(Arc::new(ImplementsParams {}), 16, 2, 0_f32)
+                .with_latency_mode(LatencyMode::ZeroLatency)
+                .with_channel_matrix(ChannelMatrix::MidSide);
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(64, |slices| {
+                *slices = vec![&mut left, &mut right];
+            })
+        }
+        proc.process(&mut buffer);
+
+        // Swapping mid and side flips the polarity of the right channel
+        for i in 0..64 {
+            assert_eq!([i as f32, -2_f32 * i as f32], [left[i], right[i]]);
+        }
+
+        // Encoding, swapping and decoding swaps three times
+        proc.set_channel_matrix(ChannelMatrix::Swapped);
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(64, |slices| {
+                *slices = vec![&mut left, &mut right];
+            })
+        }
+        proc.process(&mut buffer);
+        for i in 0..64 {
+            assert_eq!([-2_f32 * i as f32, i as f32], [left[i], right[i]]);
+        }
+    }
+
+    // The tests of the params block need `from_params`, which is not called with the `test` feature
+    #[cfg(not(feature = "test"))]
+    #[derive(Params)]
+    struct SmoothedParams {
+        value: Smoother<f32>,
+    }
+    #[cfg(not(feature = "test"))]
+    struct SmoothedBlock {
+        params: Arc<SmoothedParams>,
+        block_size: usize,
+        value: Vec<f32>,
+    }
+    #[cfg(not(feature = "test"))]
+    impl ParamsBlock for SmoothedBlock {
+        type Params = SmoothedParams;
+        fn new(params: Arc<Self::Params>, block_size: usize) -> Self {
+            Self {
+                params,
+                block_size,
+                value: vec![0_f32; block_size],
+            }
+        }
+        fn from_params(&mut self) {
+            self.params
+                .value
+                .next_block(&mut self.value, self.block_size);
+        }
+    }
+    // Outputs the param values it received
+    #[cfg(not(feature = "test"))]
+    struct RecordParams {}
+    #[cfg(not(feature = "test"))]
+    impl SingleChannelProcessor for RecordParams {
+        type ParamsBlock = SmoothedBlock;
+        fn new(
+            _block_size: usize,
+            _channel: ChannelInfo,
+            _sample_rate: f32,
+            _params: Arc<SmoothedParams>,
+        ) -> Self {
+            Self {}
+        }
+        fn process(
+            &mut self,
+            _block: &[f32],
+            output: &mut [f32],
+            params_block: &Self::ParamsBlock,
+        ) -> nih_plug::prelude::ProcessStatus {
+            output.copy_from_slice(&params_block.value);
+            nih_plug::plugin::ProcessStatus::Normal
+        }
+    }
+
+    #[cfg(not(feature = "test"))]
+    fn smoother() -> Smoother<f32> {
+        let smoother = Smoother::new(SmoothingStyle::Linear(10_f32));
+        smoother.reset(0_f32);
+        smoother.set_target(10_000_f32, 1_f32);
+        smoother
+    }
+
+    // Makes sure every channel sees the same param values, and the smoothers advance once per block rather than once per channel
+
+    #[cfg(not(feature = "test"))]
+    #[test]
+    fn test_params_block_shared_between_channels() {
+        let mut proc: DspCoreProcessor<RecordParams> = DspCoreProcessor::new(
+            Arc::new(SmoothedParams { value: smoother() }),
+            16,
+            3,
+            10_000_f32,
+        );
+
+        let mut output = vec![vec![0_f32; 160]; 3];
+        let mut start = 0;
+        for len in [75, 85] {
+            let (one, two) = output.split_at_mut(1);
+            let (two, three) = two.split_at_mut(1);
+            let mut buffer = Buffer::default();
+            unsafe {
+                buffer.set_slices(len, |slices| {
+                    *slices = vec![
+                        &mut one[0][start..start + len],
+                        &mut two[0][start..start + len],
+                        &mut three[0][start..start + len],
+                    ];
+                })
+            }
+            proc.process(&mut buffer);
+            start += len;
+        }
+
+        assert_eq!(output[0], output[1]);
+        assert_eq!(output[0], output[2]);
+
+        // The values should follow a single smoother advanced sample by sample, delayed by block_size
+        let reference = smoother();
+        for value in &output[0][16..160] {
+            assert!((value - reference.next()).abs() < 1e-6);
+        }
+    }
+
+    #[cfg(not(feature = "test"))]
+    #[test]
+    fn test_params_block_shared_between_linked_channels() {
+        let mut proc: LinkedDspCoreProcessor<crate::PerChannel<RecordParams>> =
+            LinkedDspCoreProcessor::new(
+                Arc::new(SmoothedParams { value: smoother() }),
+                32,
+                2,
+                10_000_f32,
+            )
+            .with_latency_mode(LatencyMode::ZeroLatency);
+
+        let mut output = vec![vec![0_f32; 128]; 2];
+        let (left, right) = output.split_at_mut(1);
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(128, |slices| {
+                *slices = vec![&mut left[0], &mut right[0]];
+            })
+        }
+        proc.process(&mut buffer);
+
+        assert_eq!(output[0], output[1]);
+
+        let reference = smoother();
+        for value in &output[0] {
+            assert!((value - reference.next()).abs() < 1e-6);
+        }
+    }
+
+    #[derive(Params, crate::ParamsBlock)]
+    #[params_block(name = "DerivedBlock")]
+    struct DerivedParams {
+        #[id = "gain"]
+        gain: FloatParam,
+        #[id = "drive"]
+        #[params_block(unsmoothed)]
+        drive: FloatParam,
+        #[id = "mode"]
+        mode: IntParam,
+        #[id = "enabled"]
+        enabled: BoolParam,
+        #[id = "scale"]
+        #[params_block(skip)]
+        _scale: FloatParam,
+    }
+
+    #[test]
+    fn test_derive_params_block() {
+        let params = Arc::new(DerivedParams {
+            gain: FloatParam::new(
+                "Gain",
+                1_f32,
+                FloatRange::Linear {
+                    min: 0_f32,
+                    max: 2_f32,
+                },
+            ),
+            drive: FloatParam::new(
+                "Drive",
+                0.5_f32,
+                FloatRange::Linear {
+                    min: 0_f32,
+                    max: 2_f32,
+                },
+            ),
+            mode: IntParam::new("Mode", 2, IntRange::Linear { min: 0, max: 3 }),
+            enabled: BoolParam::new("Enabled", true),
+            _scale: FloatParam::new(
+                "Scale",
+                1_f32,
+                FloatRange::Linear {
+                    min: 0_f32,
+                    max: 2_f32,
+                },
+            ),
+        });
+
+        let mut block = DerivedBlock::new(params, 8);
+        assert_eq!(8, block.block_size);
+
+        block.from_params();
+        assert_eq!(8, block.gain.len());
+        assert_eq!(vec![0.5_f32; 8], block.drive);
+        assert_eq!(8, block.mode.len());
+        assert_eq!(vec![true; 8], block.enabled);
+
+        // Only the first `block_len` samples are updated
+        block.drive.fill(0_f32);
+        block.partial_from_params(3);
+        assert_eq!([0.5_f32, 0.5_f32, 0.5_f32, 0_f32, 0_f32], block.drive[0..5]);
+    }
+
+    // Outputs the sidechain of its channel
+    struct Sidechain {}
+    impl SingleChannelProcessor for Sidechain {
+        type ParamsBlock = Block;
+        fn new(
+            _block_size: usize,
+            _channel: ChannelInfo,
+            _sample_rate: f32,
+            _params: Arc<ImplementsParams>,
+        ) -> Self {
+            Self {}
+        }
+        fn process(
+            &mut self,
+            _block: &[f32],
+            _output: &mut [f32],
+            _params_block: &Self::ParamsBlock,
+        ) -> nih_plug::prelude::ProcessStatus {
+            unreachable!()
+        }
+        fn process_with_context<S: SysExMessage>(
+            &mut self,
+            block: &[f32],
+            output: &mut [f32],
+            _params_block: &Self::ParamsBlock,
+            context: &BlockContext<f32, S>,
+        ) -> nih_plug::prelude::ProcessStatus {
+            let sidechain = context.sidechain().unwrap();
+            assert_eq!(block.len(), sidechain.len());
+
+            output.copy_from_slice(sidechain);
+            nih_plug::plugin::ProcessStatus::Normal
+        }
+    }
+
+    // Makes sure the sidechain is split into blocks aligned with the main input
+
+    #[test]
+    fn test_sidechain() {
+        let mut proc: DspCoreProcessor<Sidechain> =
+            DspCoreProcessor::new(Arc::new(ImplementsParams {}), 16, 2, 0_f32)
+                .with_aux_inputs(&[NonZeroU32::new(2).unwrap()]);
+
+        let sidechain: Vec<Vec<f32>> = vec![
+            (0..100).map(|i| i as f32).collect(),
+            (0..100).map(|i| -(i as f32)).collect(),
+        ];
+        let mut output = vec![vec![0_f32; 100]; 2];
+        let mut aux_input = sidechain.clone();
+
+        let mut start = 0;
+        for len in [10, 50, 40] {
+            let (left, right) = output.split_at_mut(1);
+            let mut buffer = Buffer::default();
+            unsafe {
+                buffer.set_slices(len, |slices| {
+                    *slices = vec![
+                        &mut left[0][start..start + len],
+                        &mut right[0][start..start + len],
+                    ];
+                })
+            }
+
+            let (aux_left, aux_right) = aux_input.split_at_mut(1);
+            let mut aux_buffer = Buffer::default();
+            unsafe {
+                aux_buffer.set_slices(len, |slices| {
+                    *slices = vec![
+                        &mut aux_left[0][start..start + len],
+                        &mut aux_right[0][start..start + len],
+                    ];
+                })
+            }
+            let mut aux = AuxiliaryBuffers {
+                inputs: &mut [aux_buffer],
+                outputs: &mut [],
+            };
+
+            proc.process_with_aux(&mut buffer, &mut aux);
+            start += len;
+        }
+
+        for channel in 0..2 {
+            assert_eq!([0_f32; 16], output[channel][0..16]);
+            assert_eq!(sidechain[channel][0..84], output[channel][16..100]);
+        }
+    }
+
+    // Writes the note number of every event onto the sample it belongs to
+    struct Notes {}
+    impl SingleChannelProcessor for Notes {
+        type ParamsBlock = Block;
+        fn new(
+            _block_size: usize,
+            _channel: ChannelInfo,
+            _sample_rate: f32,
+            _params: Arc<ImplementsParams>,
+        ) -> Self {
+            Self {}
+        }
+        fn process(
+            &mut self,
+            _block: &[f32],
+            _output: &mut [f32],
+            _params_block: &Self::ParamsBlock,
+        ) -> nih_plug::prelude::ProcessStatus {
+            unreachable!()
+        }
+        fn process_with_context<S: SysExMessage>(
+            &mut self,
+            _block: &[f32],
+            output: &mut [f32],
+            _params_block: &Self::ParamsBlock,
+            context: &BlockContext<f32, S>,
+        ) -> nih_plug::prelude::ProcessStatus {
+            output.fill(0_f32);
+            for event in context.events() {
+                if let NoteEvent::NoteOn { note, .. } = event.event {
+                    output[event.timing] = note as f32;
+                }
+            }
+            nih_plug::plugin::ProcessStatus::Normal
+        }
+    }
+
+    fn note_on(timing: u32, note: u8) -> NoteEvent<()> {
+        NoteEvent::NoteOn {
+            timing,
+            voice_id: None,
+            channel: 0,
+            note,
+            velocity: 1_f32,
+        }
+    }
+
+    // Makes sure events land on the sample they were sent with, delayed together with the audio
+
+    #[test]
+    fn test_events() {
+        let mut proc: DspCoreProcessor<Notes> =
+            DspCoreProcessor::new(Arc::new(ImplementsParams {}), 16, 2, 0_f32);
+
+        let mut output = vec![vec![0_f32; 100]; 2];
+        let mut start = 0;
+        for (len, events) in [
+            (10, vec![(0, 1), (9, 2)]),
+            (50, vec![(0, 3), (5, 4), (49, 5)]),
+            (40, vec![(30, 6)]),
+        ] {
+            for (timing, note) in events {
+                proc.queue_event(note_on(timing, note));
+            }
+
+            let (left, right) = output.split_at_mut(1);
+            let mut buffer = Buffer::default();
+            unsafe {
+                buffer.set_slices(len, |slices| {
+                    *slices = vec![
+                        &mut left[0][start..start + len],
+                        &mut right[0][start..start + len],
+                    ];
+                })
+            }
+            proc.process(&mut buffer);
+            start += len;
+        }
+
+        let mut expected = vec![0_f32; 100];
+        for (position, note) in [(0, 1), (9, 2), (10, 3), (15, 4), (59, 5), (90, 6)] {
+            if position + 16 < 100 {
+                expected[position + 16] = note as f32;
+            }
+        }
+        assert_eq!(expected, output[0]);
+        assert_eq!(expected, output[1]);
+
+        // Events that do not fit are dropped rather than allocated for
+        let counters = proc.error_counters();
+        for _ in 0..1030 {
+            proc.queue_event(note_on(0, 1));
+        }
+        assert_eq!(6, counters.dropped_events());
+    }
+
+    // Writes the position of every sample, taken from the transport of the block
+    struct Position {}
+    impl SingleChannelProcessor for Position {
+        type ParamsBlock = Block;
+        fn new(
+            _block_size: usize,
+            _channel: ChannelInfo,
+            _sample_rate: f32,
+            _params: Arc<ImplementsParams>,
+        ) -> Self {
+            Self {}
+        }
+        fn process(
+            &mut self,
+            _block: &[f32],
+            _output: &mut [f32],
+            _params_block: &Self::ParamsBlock,
+        ) -> nih_plug::prelude::ProcessStatus {
+            unreachable!()
+        }
+        fn process_with_context<S: SysExMessage>(
+            &mut self,
+            _block: &[f32],
+            output: &mut [f32],
+            _params_block: &Self::ParamsBlock,
+            context: &BlockContext<f32, S>,
+        ) -> nih_plug::prelude::ProcessStatus {
+            let start = context.transport().pos_samples.unwrap();
+            for (i, sample) in output.iter_mut().enumerate() {
+                *sample = (start + i as i64) as f32;
+            }
+            nih_plug::plugin::ProcessStatus::Normal
+        }
+    }
+
+    // The position of each block should be the one of its input samples, no matter how the host buffers are split
+
+    #[test]
+    fn test_transport() {
+        for latency_mode in [LatencyMode::Buffered, LatencyMode::ZeroLatency] {
+            let mut proc: DspCoreProcessor<Position> =
+                DspCoreProcessor::new(Arc::new(ImplementsParams {}), 16, 1, 1000_f32)
+                    .with_latency_mode(latency_mode);
+
+            let mut output = vec![0_f32; 128];
+            let mut start = 0;
+            for len in [10, 50, 4, 32, 32] {
+                proc.set_transport(TransportInfo {
+                    playing: true,
+                    sample_rate: 1000_f32,
+                    pos_samples: Some(1000 + start as i64),
+                    ..Default::default()
+                });
+
+                let mut buffer = Buffer::default();
+                unsafe {
+                    buffer.set_slices(len, |slices| {
+                        *slices = vec![&mut output[start..start + len]];
+                    })
+                }
+                proc.process(&mut buffer);
+                start += len;
+            }
+
+            let latency = proc.latency();
+            let expected: Vec<f32> = (0..128)
+                .map(|i| {
+                    if i < latency {
+                        0_f32
+                    } else {
+                        (1000 + i - latency) as f32
+                    }
+                })
+                .collect();
+            assert_eq!(expected, output);
+        }
+    }
+
+    // Adds the number of blocks processed since the last reset
+    struct Counting {
+        blocks: usize,
+    }
+    impl SingleChannelProcessor for Counting {
+        type ParamsBlock = Block;
+        fn new(
+            _block_size: usize,
+            _channel: ChannelInfo,
+            _sample_rate: f32,
+            _params: Arc<ImplementsParams>,
+        ) -> Self {
+            Self { blocks: 0 }
+        }
+        fn process(
+            &mut self,
+            block: &[f32],
+            output: &mut [f32],
+            _params_block: &Self::ParamsBlock,
+        ) -> nih_plug::prelude::ProcessStatus {
+            for (output, sample) in output.iter_mut().zip(block) {
+                *output = sample + self.blocks as f32;
+            }
+            self.blocks += 1;
+            nih_plug::plugin::ProcessStatus::Normal
+        }
+        fn reset(&mut self) {
+            self.blocks = 0;
+        }
+    }
+
+    // Makes sure no audio or processor state is left after a reset
+
+    #[test]
+    fn test_reset() {
+        let mut proc: DspCoreProcessor<Counting> =
+            DspCoreProcessor::new(Arc::new(ImplementsParams {}), 16, 1, 0_f32);
+
+        process_ramp(&mut proc, &[40]);
+        proc.reset();
+
+        let expected: Vec<f32> = (0..32)
+            .map(|i| if i < 16 { 0_f32 } else { (i - 16) as f32 })
+            .collect();
+        assert_eq!(expected, process_ramp(&mut proc, &[32]));
+    }
+
+    // Makes sure the processor gets blocks of the new size after reconfiguring
+
+    #[test]
+    fn test_reconfigure() {
+        let mut proc: DspCoreProcessor<Single> =
+            DspCoreProcessor::new(Arc::new(ImplementsParams {}), 16, 1, 0_f32);
+
+        process_ramp(&mut proc, &[40]);
+        proc.reconfigure(8, 44100_f32);
+        assert_eq!(8, proc.latency());
+
+        let expected: Vec<f32> = (0..30)
+            .map(|i| if i < 8 { 0_f32 } else { (i - 8 + 1) as f32 })
+            .collect();
+        assert_eq!(expected, process_ramp(&mut proc, &[3, 27]));
+    }
+
+    // Would lose the fractional part of the input when processed in f32
+    struct Double {}
+    impl SingleChannelProcessor<f64> for Double {
+        type ParamsBlock = Block;
+        fn new(
+            _block_size: usize,
+            _channel: ChannelInfo,
+            _sample_rate: f32,
+            _params: Arc<ImplementsParams>,
+        ) -> Self {
+            Self {}
+        }
+        fn process(
+            &mut self,
+            block: &[f64],
+            output: &mut [f64],
+            _params_block: &Self::ParamsBlock,
+        ) -> nih_plug::prelude::ProcessStatus {
+            for (output, &sample) in output.iter_mut().zip(block) {
+                *output = (sample + 1e8_f64) - 1e8_f64 + 1_f64;
+            }
+            nih_plug::plugin::ProcessStatus::Normal
+        }
+    }
+
+    // Makes sure the host buffers are converted to the sample type of the processor and back
+
+    #[test]
+    fn test_f64_processing() {
+        let mut proc: DspCoreProcessor<Double, f64> =
+            DspCoreProcessor::new(Arc::new(ImplementsParams {}), 16, 1, 0_f32);
+
+        let mut output: Vec<f32> = (0..100).map(|i| i as f32 * 0.25_f32).collect();
+        for block in output.chunks_mut(30) {
+            let mut buffer = Buffer::default();
+            unsafe {
+                buffer.set_slices(block.len(), |slices| {
+                    *slices = vec![block];
+                })
+            }
+            proc.process(&mut buffer);
+        }
+
+        let expected: Vec<f32> = (0..100)
+            .map(|i| {
+                if i < 16 {
+                    0_f32
+                } else {
+                    (i - 16) as f32 * 0.25_f32 + 1_f32
+                }
+            })
+            .collect();
+        assert_eq!(expected, output);
+    }
 }
@@ -0,0 +1,114 @@
+use crate::Sample;
+
+/// Stereo matrix applied by [`crate::LinkedDspCoreProcessor`] around the processor
+///
+/// The first two channels are encoded before every block is processed, and decoded after, so e.g. with
+/// [`ChannelMatrix::MidSide`] the processor receives mid in channel `0` and side in channel `1`. Other channels are passed
+/// as they are. The matrix is applied per sample, so it adds no latency.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum ChannelMatrix {
+    /// Left and right, as received from the host
+    #[default]
+    LeftRight,
+    /// Mid `(L + R) / 2` and side `(L - R) / 2`
+    MidSide,
+    /// Right and left
+    Swapped,
+    /// Custom 2x2 matrices, `encode[row][column]`, so the first encoded channel is `encode[0][0] * L + encode[0][1] * R`.
+    ///
+    /// `decode` should be the inverse of `encode`, see [`ChannelMatrix::custom`].
+    Custom {
+        /// Matrix applied to the input
+        encode: [[f32; 2]; 2],
+        /// Matrix applied to the output
+        decode: [[f32; 2]; 2],
+    },
+}
+
+impl ChannelMatrix {
+    /// Custom matrix decoding with the inverse of `encode`
+    ///
+    /// Returns `None` if `encode` cannot be inverted.
+    pub fn custom(encode: [[f32; 2]; 2]) -> Option<Self> {
+        let determinant = encode[0][0] * encode[1][1] - encode[0][1] * encode[1][0];
+        if determinant.abs() < f32::EPSILON {
+            return None;
+        }
+
+        Some(Self::Custom {
+            encode,
+            decode: [
+                [encode[1][1] / determinant, -encode[0][1] / determinant],
+                [-encode[1][0] / determinant, encode[0][0] / determinant],
+            ],
+        })
+    }
+
+    /// Encode the first two `blocks` in place
+    pub fn encode<T: Sample>(&self, blocks: &mut [Vec<T>]) {
+        match *self {
+            Self::LeftRight => {}
+            Self::MidSide => apply(blocks, [[0.5, 0.5], [0.5, -0.5]]),
+            Self::Swapped => apply(blocks, [[0.0, 1.0], [1.0, 0.0]]),
+            Self::Custom { encode, .. } => apply(blocks, encode),
+        }
+    }
+
+    /// Decode the first two `blocks` in place
+    pub fn decode<T: Sample>(&self, blocks: &mut [Vec<T>]) {
+        match *self {
+            Self::LeftRight => {}
+            Self::MidSide => apply(blocks, [[1.0, 1.0], [1.0, -1.0]]),
+            Self::Swapped => apply(blocks, [[0.0, 1.0], [1.0, 0.0]]),
+            Self::Custom { decode, .. } => apply(blocks, decode),
+        }
+    }
+}
+
+fn apply<T: Sample>(blocks: &mut [Vec<T>], matrix: [[f32; 2]; 2]) {
+    let [first, second, ..] = blocks else {
+        return;
+    };
+    let [[a, b], [c, d]] = matrix.map(|row| row.map(T::from_f32));
+
+    for (first, second) in first.iter_mut().zip(second.iter_mut()) {
+        let (left, right) = (*first, *second);
+        *first = a * left + b * right;
+        *second = c * left + d * right;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ChannelMatrix;
+
+    #[test]
+    fn test_matrix() {
+        let input = vec![vec![1_f32, 0.5_f32, -2_f32], vec![0_f32, 0.5_f32, 3_f32]];
+
+        let mut blocks = input.clone();
+        ChannelMatrix::MidSide.encode(&mut blocks);
+        assert_eq!(vec![0.5_f32, 0.5_f32, 0.5_f32], blocks[0]);
+        assert_eq!(vec![0.5_f32, 0_f32, -2.5_f32], blocks[1]);
+
+        let skew = ChannelMatrix::custom([[2_f32, 1_f32], [1_f32, 1_f32]]).unwrap();
+        for matrix in [
+            ChannelMatrix::LeftRight,
+            ChannelMatrix::MidSide,
+            ChannelMatrix::Swapped,
+            skew,
+        ] {
+            let mut blocks = input.clone();
+            matrix.encode(&mut blocks);
+            matrix.decode(&mut blocks);
+            for (decoded, input) in blocks.iter().flatten().zip(input.iter().flatten()) {
+                assert!((decoded - input).abs() < 1e-6_f32);
+            }
+        }
+
+        assert_eq!(
+            None,
+            ChannelMatrix::custom([[1_f32, 2_f32], [2_f32, 4_f32]])
+        );
+    }
+}
@@ -0,0 +1,455 @@
+use std::f32::consts::TAU;
+
+use crate::ParamsBlock;
+use crate::Sample;
+use crate::TransportInfo;
+
+// Tempo used by tempo synced sources when the host does not provide one
+const DEFAULT_TEMPO: f64 = 120_f64;
+
+/// Speed of a periodic modulation source
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Rate {
+    /// Cycles per second
+    Hz(f32),
+    /// Length of a cycle in quarter notes, following the host tempo
+    ///
+    /// While the transport is playing, the phase is locked to the host position, so the source lines up with the bars.
+    Beats(f32),
+}
+
+impl Rate {
+    // Cycles per sample
+    //
+    // Non-positive rates, and tempos that give no samples per beat, stop the source rather than producing a NaN phase
+    fn increment(&self, transport: &TransportInfo) -> f64 {
+        if transport.sample_rate <= 0_f32 {
+            return 0_f64;
+        }
+        let increment = match *self {
+            Rate::Hz(hz) => hz as f64 / transport.sample_rate as f64,
+            Rate::Beats(beats) => {
+                let samples_per_beat = transport
+                    .samples_per_beat()
+                    .unwrap_or(60_f64 / DEFAULT_TEMPO * transport.sample_rate as f64);
+                1_f64 / (beats as f64 * samples_per_beat)
+            }
+        };
+        if increment.is_finite() && increment > 0_f64 {
+            increment
+        } else {
+            0_f64
+        }
+    }
+
+    // Phase at the host position, wrapped to `cycles`, if the rate follows the tempo and the transport is playing
+    fn synced_phase(&self, transport: &TransportInfo, cycles: f64) -> Option<f64> {
+        match *self {
+            Rate::Beats(beats) if transport.playing && beats > 0_f32 => transport
+                .pos_beats
+                .map(|pos| (pos / beats as f64).rem_euclid(cycles)),
+            _ => None,
+        }
+    }
+}
+
+/// What a [`ModulationSource`] gets to compute a block
+pub struct ModulationContext<'a> {
+    /// Level of the input, the highest absolute value of all channels for every sample of the block
+    pub level: &'a [f32],
+    /// Transport and tempo at the first sample of the block
+    pub transport: &'a TransportInfo,
+}
+
+/// Source of modulation, producing one value per sample
+///
+/// Add sources to a [`Modulation`]. [`Lfo`], [`EnvelopeFollower`] and [`StepSequencer`] are provided, implement it for your
+/// own sources. Periodic sources output values in `-1.0..=1.0`.
+pub trait ModulationSource: Send {
+    /// Fill `output` with the values for the samples of the block
+    fn next_block(&mut self, output: &mut [f32], context: &ModulationContext);
+
+    /// Restart the source, called from [`crate::LinkedDspCoreProcessor::reset`]
+    fn reset(&mut self) {}
+}
+
+/// Waveform of an [`Lfo`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LfoShape {
+    /// Sine, starting at zero and rising
+    Sine,
+    /// Triangle, starting at zero and rising
+    Triangle,
+    /// Rising saw
+    Saw,
+    /// Square, high for the first half of the cycle
+    Square,
+}
+
+impl LfoShape {
+    // Value at `phase`, in cycles. All shapes start at zero and rise, except the square
+    fn value(&self, phase: f32) -> f32 {
+        match self {
+            LfoShape::Sine => (phase * TAU).sin(),
+            LfoShape::Triangle => {
+                1_f32 - 4_f32 * ((phase + 0.25_f32).rem_euclid(1_f32) - 0.5_f32).abs()
+            }
+            LfoShape::Saw => 2_f32 * (phase + 0.5_f32).rem_euclid(1_f32) - 1_f32,
+            LfoShape::Square => {
+                if phase < 0.5_f32 {
+                    1_f32
+                } else {
+                    -1_f32
+                }
+            }
+        }
+    }
+}
+
+/// Low frequency oscillator
+pub struct Lfo {
+    shape: LfoShape,
+    rate: Rate,
+    offset: f64,
+    phase: f64,
+}
+
+impl Lfo {
+    /// Oscillate with `shape` at `rate`, starting at the beginning of the cycle, see [`Self::with_phase`]
+    pub fn new(shape: LfoShape, rate: Rate) -> Self {
+        Self {
+            shape,
+            rate,
+            offset: 0_f64,
+            phase: 0_f64,
+        }
+    }
+
+    /// Set the starting phase, in cycles, `0.0` by default
+    pub fn with_phase(mut self, phase: f32) -> Self {
+        self.offset = (phase as f64).rem_euclid(1_f64);
+        self.phase = self.offset;
+        self
+    }
+}
+
+impl ModulationSource for Lfo {
+    fn next_block(&mut self, output: &mut [f32], context: &ModulationContext) {
+        if let Some(phase) = self.rate.synced_phase(context.transport, 1_f64) {
+            self.phase = (phase + self.offset).rem_euclid(1_f64);
+        }
+        let increment = self.rate.increment(context.transport);
+        for value in output.iter_mut() {
+            *value = self.shape.value(self.phase as f32);
+            self.phase = (self.phase + increment).rem_euclid(1_f64);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.phase = self.offset;
+    }
+}
+
+/// Level of the input, rising with the attack time and falling with the release time
+///
+/// Outputs the level as gain, `0.0` for silence.
+pub struct EnvelopeFollower {
+    attack_ms: f32,
+    release_ms: f32,
+    envelope: f32,
+}
+
+impl EnvelopeFollower {
+    /// Follow the input with the attack and release times in milliseconds
+    pub fn new(attack_ms: f32, release_ms: f32) -> Self {
+        Self {
+            attack_ms,
+            release_ms,
+            envelope: 0_f32,
+        }
+    }
+}
+
+// One pole coefficient reaching ~63% of a step after `ms`
+fn coefficient(ms: f32, sample_rate: f32) -> f32 {
+    let samples = ms * 0.001_f32 * sample_rate;
+    if samples <= 0_f32 {
+        0_f32
+    } else {
+        (-1_f32 / samples).exp()
+    }
+}
+
+impl ModulationSource for EnvelopeFollower {
+    fn next_block(&mut self, output: &mut [f32], context: &ModulationContext) {
+        let attack = coefficient(self.attack_ms, context.transport.sample_rate);
+        let release = coefficient(self.release_ms, context.transport.sample_rate);
+        for (value, &level) in output.iter_mut().zip(context.level) {
+            let coefficient = if level > self.envelope {
+                attack
+            } else {
+                release
+            };
+            self.envelope = level + coefficient * (self.envelope - level);
+            *value = self.envelope;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.envelope = 0_f32;
+    }
+}
+
+/// Sequence of values, held for one step each
+pub struct StepSequencer {
+    steps: Vec<f32>,
+    rate: Rate,
+    phase: f64,
+}
+
+impl StepSequencer {
+    /// Play `steps` in a loop, with `rate` being the speed of a single step
+    pub fn new(steps: Vec<f32>, rate: Rate) -> Self {
+        assert!(!steps.is_empty(), "Step sequencer needs at least one step");
+        Self {
+            steps,
+            rate,
+            phase: 0_f64,
+        }
+    }
+}
+
+impl ModulationSource for StepSequencer {
+    fn next_block(&mut self, output: &mut [f32], context: &ModulationContext) {
+        let steps = self.steps.len() as f64;
+        if let Some(phase) = self.rate.synced_phase(context.transport, steps) {
+            self.phase = phase;
+        }
+        let increment = self.rate.increment(context.transport);
+        for value in output.iter_mut() {
+            *value = self.steps[(self.phase as usize).min(self.steps.len() - 1)];
+            self.phase = (self.phase + increment).rem_euclid(steps);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.phase = 0_f64;
+    }
+}
+
+// Block of params a route modulates
+type ModulationTarget<B> = fn(&mut B) -> &mut [f32];
+
+// Connection from a source to a block of params
+struct Route<B> {
+    source: usize,
+    target: ModulationTarget<B>,
+    depth: f32,
+}
+
+/// Sources of modulation and the params they modulate, applied by [`crate::LinkedDspCoreProcessor::with_modulation`]
+///
+/// Every block, after the [`ParamsBlock`] is filled from the params, each route adds the values of its source times its
+/// depth to its target block. The processor then sees the modulated values. Depth is in the units of the param, e.g. Hz for
+/// a cutoff. Like reading the params, modulation is skipped with the `test` feature.
+///
+/// ```no_run
+/// let modulation = Modulation::new()
+///     .with_source(Lfo::new(LfoShape::Sine, Rate::Beats(4_f32)))
+///     .with_route(0, |params_block: &mut PluginParamsBlock| &mut params_block.cutoff, 2000_f32);
+/// ```
+pub struct Modulation<B> {
+    sources: Vec<Box<dyn ModulationSource>>,
+    routes: Vec<Route<B>>,
+    values: Vec<Vec<f32>>,
+    level: Vec<f32>,
+}
+
+impl<B> Default for Modulation<B> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<B> Modulation<B> {
+    /// Modulation without sources or routes, add them with [`Self::with_source`] and [`Self::with_route`]
+    pub fn new() -> Self {
+        Self {
+            sources: Vec::new(),
+            routes: Vec::new(),
+            values: Vec::new(),
+            level: Vec::new(),
+        }
+    }
+
+    /// Add a source. Sources are numbered in the order they are added, starting at `0`
+    pub fn with_source(mut self, source: impl ModulationSource + 'static) -> Self {
+        self.sources.push(Box::new(source));
+        self
+    }
+
+    /// Modulate the block returned by `target` with source number `source`, scaled by `depth`
+    pub fn with_route(mut self, source: usize, target: ModulationTarget<B>, depth: f32) -> Self {
+        assert!(
+            source < self.sources.len(),
+            "No modulation source {}",
+            source
+        );
+        self.routes.push(Route {
+            source,
+            target,
+            depth,
+        });
+        self
+    }
+
+    /// Change the depth of route number `route`, numbered in the order they were added
+    pub fn set_depth(&mut self, route: usize, depth: f32) {
+        self.routes[route].depth = depth;
+    }
+
+    // Allocates the blocks of source values
+    pub(crate) fn allocate(&mut self, block_size: usize) {
+        self.values = vec![vec![0_f32; block_size]; self.sources.len()];
+        self.level = vec![0_f32; block_size];
+    }
+
+    pub(crate) fn reset(&mut self) {
+        for source in self.sources.iter_mut() {
+            source.reset();
+        }
+    }
+}
+
+impl<B: ParamsBlock> Modulation<B> {
+    // Adds the modulation to the first `len` samples of the params block, following the level of `input`
+    #[cfg_attr(feature = "test", allow(dead_code))]
+    pub(crate) fn apply<T: Sample>(
+        &mut self,
+        params_block: &mut B,
+        input: &[Vec<T>],
+        len: usize,
+        transport: &TransportInfo,
+    ) {
+        if self.routes.is_empty() {
+            return;
+        }
+
+        let level = &mut self.level[0..len];
+        level.fill(0_f32);
+        for channel in input {
+            for (level, sample) in level.iter_mut().zip(channel) {
+                *level = level.max(sample.to_f32().abs());
+            }
+        }
+
+        let context = ModulationContext { level, transport };
+        for (source, values) in self.sources.iter_mut().zip(self.values.iter_mut()) {
+            source.next_block(&mut values[0..len], &context);
+        }
+
+        for route in self.routes.iter() {
+            let target = (route.target)(params_block);
+            for (value, modulation) in target[0..len].iter_mut().zip(&self.values[route.source]) {
+                *value += route.depth * modulation;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        EnvelopeFollower, Lfo, LfoShape, ModulationContext, ModulationSource, Rate, StepSequencer,
+    };
+    use crate::TransportInfo;
+
+    fn run(source: &mut impl ModulationSource, transport: &TransportInfo, len: usize) -> Vec<f32> {
+        let mut output = vec![0_f32; len];
+        source.next_block(
+            &mut output,
+            &ModulationContext {
+                level: &vec![1_f32; len],
+                transport,
+            },
+        );
+        output
+    }
+
+    #[test]
+    fn test_lfo() {
+        let transport = TransportInfo {
+            sample_rate: 8_f32,
+            ..Default::default()
+        };
+        let mut lfo = Lfo::new(LfoShape::Triangle, Rate::Hz(2_f32));
+        assert_eq!(
+            vec![0_f32, 1_f32, 0_f32, -1_f32, 0_f32],
+            run(&mut lfo, &transport, 5)
+        );
+
+        // 2 beats per cycle at 60 BPM is 16 samples
+        let mut lfo = Lfo::new(LfoShape::Square, Rate::Beats(2_f32));
+        let transport = TransportInfo {
+            tempo: Some(60_f64),
+            ..transport
+        };
+        assert_eq!([1_f32; 8], run(&mut lfo, &transport, 16)[0..8]);
+
+        // Follows the host position while playing
+        let transport = TransportInfo {
+            playing: true,
+            pos_beats: Some(5_f64),
+            ..transport
+        };
+        assert_eq!([-1_f32; 8], run(&mut lfo, &transport, 8)[0..8]);
+    }
+
+    // Makes sure rates without a usable increment hold the phase, and negative phases stay in range
+
+    #[test]
+    fn test_lfo_invalid_rate() {
+        let transport = TransportInfo {
+            sample_rate: 8_f32,
+            ..Default::default()
+        };
+        for rate in [Rate::Beats(0_f32), Rate::Hz(-2_f32), Rate::Hz(f32::NAN)] {
+            let mut lfo = Lfo::new(LfoShape::Saw, rate).with_phase(-0.25_f32);
+            assert_eq!(vec![-0.5_f32; 4], run(&mut lfo, &transport, 4));
+        }
+    }
+
+    #[test]
+    fn test_envelope_follower() {
+        let transport = TransportInfo {
+            sample_rate: 1000_f32,
+            ..Default::default()
+        };
+        let mut follower = EnvelopeFollower::new(0_f32, 10_f32);
+        assert_eq!(1_f32, run(&mut follower, &transport, 1)[0]);
+
+        // Falls to ~37% after the release time
+        let mut output = vec![0_f32; 10];
+        follower.next_block(
+            &mut output,
+            &ModulationContext {
+                level: &[0_f32; 10],
+                transport: &transport,
+            },
+        );
+        assert!((output[9] - (-1_f32).exp()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_step_sequencer() {
+        let transport = TransportInfo {
+            sample_rate: 4_f32,
+            ..Default::default()
+        };
+        let mut sequencer = StepSequencer::new(vec![1_f32, 2_f32, 3_f32], Rate::Hz(2_f32));
+        assert_eq!(
+            vec![1_f32, 1_f32, 2_f32, 2_f32, 3_f32, 3_f32, 1_f32],
+            run(&mut sequencer, &transport, 7)
+        );
+    }
+}
@@ -0,0 +1,634 @@
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use nih_plug::prelude::ProcessStatus;
+use nih_plug::prelude::SysExMessage;
+
+use crate::algorithms::FilterPhase;
+use crate::algorithms::Oversampler;
+use crate::delay::shift_block;
+use crate::delay::DelayLine;
+use crate::delay::LookaheadBuffer;
+use crate::error::to_status;
+use crate::BlockContext;
+use crate::ChannelInfo;
+use crate::ParamsBlock;
+use crate::ProcessError;
+use crate::Sample;
+use crate::SingleChannelProcessor;
+
+/// Type level selection of the [`FilterPhase`] used by [`Oversampled`]
+pub trait OversamplingPhase {
+    /// Phase response of the filters
+    const PHASE: FilterPhase;
+}
+
+/// Linear phase oversampling filters, see [`FilterPhase::Linear`]
+pub struct LinearPhase;
+
+impl OversamplingPhase for LinearPhase {
+    const PHASE: FilterPhase = FilterPhase::Linear;
+}
+
+/// Minimum phase oversampling filters, see [`FilterPhase::Minimum`]
+pub struct MinimumPhase;
+
+impl OversamplingPhase for MinimumPhase {
+    const PHASE: FilterPhase = FilterPhase::Minimum;
+}
+
+/// Runs the wrapped [`SingleChannelProcessor`] at `FACTOR` times the sample rate.
+///
+/// `FACTOR` can be `2`, `4`, `8` or `16`. Every block is upsampled with an [`Oversampler`], processed, and downsampled back,
+/// so the wrapped processor gets created with `block_size * FACTOR` and `sample_rate * FACTOR`. The delay of the filters
+/// is added to [`SingleChannelProcessor::latency`]. The latency of the wrapped processor is padded to whole host samples
+/// at the oversampled rate, so the reported latency is exact.
+///
+/// The parameters are filled at the host rate, so smoothers advance once per host sample, and every value is then
+/// spread over `FACTOR` samples with [`OversamplingParamsBlock::upsample_from`]. The [`BlockContext`] follows the higher
+/// rate too: auxiliary inputs are upsampled, event timings are multiplied by `FACTOR`, and the transport reports the
+/// oversampled rate. Only the auxiliary inputs given to [`SingleChannelProcessor::set_aux_inputs`] are upsampled, their
+/// buffers and filters are set up there rather than while processing. The lookahead of the wrapped processor is rounded up to whole host samples, and its upcoming samples
+/// are upsampled as well.
+///
+/// # Examples
+///
+/// ```no_run
+/// // Saturator running at 4x the host sample rate, with linear phase filters
+/// type Processor = DspCoreProcessor<Oversampled<Saturator, 4>>;
+///
+/// // Same with minimum phase filters
+/// type LowLatencyProcessor = DspCoreProcessor<Oversampled<Saturator, 4, MinimumPhase>>;
+///
+/// // Double precision saturator, the sample type goes last
+/// type PreciseProcessor = DspCoreProcessor<Oversampled<PreciseSaturator, 4, LinearPhase, f64>, f64>;
+/// ```
+pub struct Oversampled<
+    SCP: SingleChannelProcessor<T>,
+    const FACTOR: usize,
+    P: OversamplingPhase = LinearPhase,
+    T: Sample = f32,
+> {
+    processor: SCP,
+    params_block: SCP::ParamsBlock,
+    oversampler: Oversampler<T>,
+    upsampled: Vec<T>,
+    processed: Vec<T>,
+    block_size: usize,
+    // The block followed by its upcoming samples, to upsample the newest samples
+    ahead: Vec<T>,
+    // Delays the upsampled blocks by the lookahead, keeping the upcoming samples the wrapped processor looks ahead into
+    upsampled_lookahead: LookaheadBuffer<T>,
+    // Pads the latency of the wrapped processor to whole host samples
+    latency_padding: DelayLine<T>,
+    // Upsampled blocks of every channel of every auxiliary input, with their oversamplers
+    aux_inputs: Vec<Vec<Vec<T>>>,
+    aux_oversamplers: Vec<Vec<Oversampler<T>>>,
+    phase: PhantomData<P>,
+}
+
+impl<SCP, const FACTOR: usize, P: OversamplingPhase, T: Sample> Oversampled<SCP, FACTOR, P, T>
+where
+    SCP: SingleChannelProcessor<T>,
+    SCP::ParamsBlock: OversamplingParamsBlock,
+{
+    // Upsamples the first `len` samples of the auxiliary inputs of `context` that were set up
+    fn upsample_aux<S: SysExMessage>(&mut self, context: &BlockContext<T, S>, len: usize) {
+        for (port, (blocks, oversamplers)) in self
+            .aux_inputs
+            .iter_mut()
+            .zip(self.aux_oversamplers.iter_mut())
+            .enumerate()
+        {
+            let Some(input) = context.aux_input(port) else {
+                continue;
+            };
+            for ((block, oversampler), input) in blocks.iter_mut().zip(oversamplers).zip(input) {
+                oversampler.upsample(&input[0..len], &mut block[0..len * FACTOR]);
+            }
+        }
+    }
+}
+
+impl<SCP, const FACTOR: usize, P: OversamplingPhase, T: Sample> SingleChannelProcessor<T>
+    for Oversampled<SCP, FACTOR, P, T>
+where
+    SCP: SingleChannelProcessor<T>,
+    SCP::ParamsBlock: OversamplingParamsBlock,
+{
+    type ParamsBlock = OversampledBlock<SCP::ParamsBlock, FACTOR>;
+
+    fn new(
+        block_size: usize,
+        channel: ChannelInfo,
+        sample_rate: f32,
+        params: Arc<<Self::ParamsBlock as ParamsBlock>::Params>,
+    ) -> Self {
+        let processor = SCP::new(
+            block_size * FACTOR,
+            channel,
+            sample_rate * FACTOR as f32,
+            params.clone(),
+        );
+        let lookahead = processor.lookahead().div_ceil(FACTOR);
+        let latency_padding = (FACTOR - processor.latency() % FACTOR) % FACTOR;
+
+        Self {
+            processor,
+            params_block: SCP::ParamsBlock::new(params, block_size * FACTOR),
+            oversampler: Oversampler::new(FACTOR, P::PHASE, block_size),
+            upsampled: vec![T::ZERO; block_size * FACTOR],
+            processed: vec![T::ZERO; block_size * FACTOR],
+            block_size,
+            ahead: vec![T::ZERO; block_size + lookahead],
+            upsampled_lookahead: LookaheadBuffer::new(lookahead * FACTOR, block_size * FACTOR),
+            latency_padding: DelayLine::new(latency_padding),
+            aux_inputs: Vec::new(),
+            aux_oversamplers: Vec::new(),
+            phase: PhantomData,
+        }
+    }
+
+    fn process(
+        &mut self,
+        block: &[T],
+        output: &mut [T],
+        params_block: &Self::ParamsBlock,
+    ) -> ProcessStatus {
+        self.process_with_context(
+            block,
+            output,
+            params_block,
+            &BlockContext::<T, ()>::default(),
+        )
+    }
+
+    fn process_with_context<S: SysExMessage>(
+        &mut self,
+        block: &[T],
+        output: &mut [T],
+        params_block: &Self::ParamsBlock,
+        context: &BlockContext<T, S>,
+    ) -> ProcessStatus {
+        to_status(self.try_process(block, output, params_block, context))
+    }
+
+    fn try_process<S: SysExMessage>(
+        &mut self,
+        block: &[T],
+        output: &mut [T],
+        params_block: &Self::ParamsBlock,
+        context: &BlockContext<T, S>,
+    ) -> Result<(), ProcessError> {
+        let block_len = block.len();
+        let len = block_len * FACTOR;
+        let lookahead = self.upsampled_lookahead.lookahead() / FACTOR;
+
+        // With a lookahead the newest samples are upsampled, and delayed back at the higher rate, so the upcoming samples
+        // the wrapped processor sees went through the same filters as its block
+        let block = if lookahead == 0 {
+            block
+        } else {
+            shift_block(&mut self.ahead, block, context.lookahead(), lookahead, 0).0
+        };
+        self.oversampler
+            .upsample(block, &mut self.upsampled[0..len]);
+        self.params_block
+            .upsample_from(&params_block.inner, block_len, FACTOR);
+        self.upsample_aux(context, block_len);
+
+        let context = context.oversampled(&self.aux_inputs, FACTOR);
+        let result = if lookahead == 0 {
+            self.processor.try_process(
+                &self.upsampled[0..len],
+                &mut self.processed[0..len],
+                &self.params_block,
+                &context,
+            )
+        } else {
+            let (upsampled, upcoming) = self.upsampled_lookahead.push(&self.upsampled[0..len]);
+            self.processor.try_process(
+                upsampled,
+                &mut self.processed[0..len],
+                &self.params_block,
+                &context.with_lookahead(&upcoming[0..self.processor.lookahead()]),
+            )
+        };
+        self.latency_padding.process(&mut self.processed[0..len]);
+        self.oversampler.downsample(&self.processed[0..len], output);
+
+        result
+    }
+
+    fn latency(&self) -> usize {
+        self.oversampler.latency() + self.processor.latency().div_ceil(FACTOR)
+    }
+
+    fn lookahead(&self) -> usize {
+        self.processor.lookahead().div_ceil(FACTOR)
+    }
+
+    fn tail(&self) -> usize {
+        match self.processor.tail() {
+            usize::MAX => usize::MAX,
+            tail => tail.div_ceil(FACTOR),
+        }
+    }
+
+    fn accepts_variable_block_size(&self) -> bool {
+        self.processor.accepts_variable_block_size()
+    }
+
+    fn reset(&mut self) {
+        self.oversampler.reset();
+        for oversampler in self.aux_oversamplers.iter_mut().flatten() {
+            oversampler.reset();
+        }
+        self.upsampled_lookahead.reset();
+        self.latency_padding.reset();
+        self.processor.reset();
+    }
+
+    fn suspend(&mut self) {
+        self.processor.suspend();
+    }
+
+    // The aux oversamplers are copies of the main one, so the filters are only designed once
+    fn set_aux_inputs(&mut self, aux_input_ports: &[usize]) {
+        let len = self.block_size * FACTOR;
+        let mut oversampler = self.oversampler.clone();
+        oversampler.reset();
+
+        self.aux_inputs = aux_input_ports
+            .iter()
+            .map(|&channels| vec![vec![T::ZERO; len]; channels])
+            .collect();
+        self.aux_oversamplers = aux_input_ports
+            .iter()
+            .map(|&channels| vec![oversampler.clone(); channels])
+            .collect();
+        self.processor.set_aux_inputs(aux_input_ports);
+    }
+}
+
+/// [`ParamsBlock`] that can be used by a processor wrapped in [`Oversampled`]
+///
+/// The block is filled at the host rate, then its values are spread over the oversampled block.
+///
+/// # Examples
+///
+/// ```no_run
+/// impl OversamplingParamsBlock for GainBlock {
+///     fn upsample_from(&mut self, source: &Self, len: usize, factor: usize) {
+///         hold(&mut self.gain[0..len * factor], &source.gain[0..len], factor);
+///     }
+/// }
+/// ```
+pub trait OversamplingParamsBlock: ParamsBlock {
+    /// Fill the first `len * factor` values of every block from the first `len` values of `source`
+    ///
+    /// Use [`hold`] to repeat every value `factor` times.
+    fn upsample_from(&mut self, source: &Self, len: usize, factor: usize);
+}
+
+/// Repeats every value of `input` `factor` times into `output`
+pub fn hold<V: Copy>(output: &mut [V], input: &[V], factor: usize) {
+    for (output, value) in output.chunks_mut(factor).zip(input) {
+        output.fill(*value);
+    }
+}
+
+/// [`ParamsBlock`] of an [`Oversampled`] processor
+///
+/// Wraps the [`ParamsBlock`] of the inner processor at the host rate, so it is filled with the same values as without
+/// oversampling.
+pub struct OversampledBlock<PB: ParamsBlock, const FACTOR: usize> {
+    inner: PB,
+}
+
+impl<PB: ParamsBlock, const FACTOR: usize> OversampledBlock<PB, FACTOR> {
+    /// The [`ParamsBlock`] of the inner processor, before upsampling
+    pub fn inner(&self) -> &PB {
+        &self.inner
+    }
+
+    /// Mutable access to the [`ParamsBlock`] of the inner processor, e.g. for [`crate::Modulation`] targets
+    pub fn inner_mut(&mut self) -> &mut PB {
+        &mut self.inner
+    }
+}
+
+impl<PB: ParamsBlock, const FACTOR: usize> ParamsBlock for OversampledBlock<PB, FACTOR> {
+    type Params = PB::Params;
+
+    fn new(params: Arc<Self::Params>, block_size: usize) -> Self {
+        Self {
+            inner: PB::new(params, block_size),
+        }
+    }
+
+    fn from_params(&mut self) {
+        self.inner.from_params();
+    }
+
+    fn partial_from_params(&mut self, block_len: usize) {
+        self.inner.partial_from_params(block_len);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use nih_plug::buffer::Buffer;
+    use nih_plug::params::Params;
+    use nih_plug::prelude::{NoteEvent, ProcessStatus, SysExMessage};
+
+    use super::{hold, MinimumPhase, Oversampled, OversamplingParamsBlock};
+    use crate::delay::DelayLine;
+    use crate::{
+        BlockContext, BlockEvent, ChannelInfo, DspCoreProcessor, ParamsBlock,
+        SingleChannelProcessor, TransportInfo,
+    };
+
+    #[derive(Params)]
+    struct ImplementsParams {}
+
+    // Counts up by one per sample, like a smoother that never settles
+    struct Block {
+        ramp: Vec<f32>,
+        next: f32,
+    }
+    impl ParamsBlock for Block {
+        type Params = ImplementsParams;
+        fn new(_params: Arc<Self::Params>, block_size: usize) -> Self {
+            Self {
+                ramp: vec![0_f32; block_size],
+                next: 0_f32,
+            }
+        }
+        fn from_params(&mut self) {
+            for value in self.ramp.iter_mut() {
+                *value = self.next;
+                self.next += 1_f32;
+            }
+        }
+    }
+    impl OversamplingParamsBlock for Block {
+        fn upsample_from(&mut self, source: &Self, len: usize, factor: usize) {
+            hold(
+                &mut self.ramp[0..len * factor],
+                &source.ramp[0..len],
+                factor,
+            );
+        }
+    }
+
+    // Passes the signal through, checking it runs at the oversampled rate
+    struct Identity {
+        block_size: usize,
+    }
+    impl SingleChannelProcessor for Identity {
+        type ParamsBlock = Block;
+        fn new(
+            block_size: usize,
+            _channel: ChannelInfo,
+            sample_rate: f32,
+            _params: Arc<ImplementsParams>,
+        ) -> Self {
+            assert_eq!(4_f32 * 1000_f32, sample_rate);
+            Self { block_size }
+        }
+        fn process(
+            &mut self,
+            block: &[f32],
+            output: &mut [f32],
+            _params_block: &Self::ParamsBlock,
+        ) -> ProcessStatus {
+            assert_eq!(self.block_size, block.len());
+            output.copy_from_slice(block);
+            ProcessStatus::Normal
+        }
+    }
+
+    fn process_sine<
+        SCP: SingleChannelProcessor<ParamsBlock = super::OversampledBlock<Block, 4>>,
+    >() -> (Vec<f32>, Vec<f32>, usize) {
+        let mut proc: DspCoreProcessor<SCP> =
+            DspCoreProcessor::new(Arc::new(ImplementsParams {}), 32, 1, 1000_f32);
+
+        let input: Vec<f32> = (0..1024)
+            .map(|i| (2_f32 * std::f32::consts::PI * 0.02_f32 * i as f32).sin())
+            .collect();
+        let mut output = input.clone();
+        for block in output.chunks_mut(100) {
+            let mut buffer = Buffer::default();
+            unsafe {
+                buffer.set_slices(block.len(), |slices| {
+                    *slices = vec![block];
+                })
+            }
+            proc.process(&mut buffer);
+        }
+        (input, output, proc.latency())
+    }
+
+    // Makes sure the wrapped processor gets the scaled block size and sample rate, and the reported latency is right
+
+    #[test]
+    fn test_oversampled() {
+        let (input, output, latency) = process_sine::<Oversampled<Identity, 4>>();
+        assert!(latency > 32);
+        for i in 300..1024 {
+            assert!((output[i] - input[i - latency]).abs() < 1e-3_f32);
+        }
+
+        let (_, _, minimum_latency) = process_sine::<Oversampled<Identity, 4, MinimumPhase>>();
+        assert!(minimum_latency < latency);
+    }
+
+    // Delays the signal by 3 samples at the oversampled rate, less than a host sample
+    struct Delayed {
+        delay: DelayLine<f32>,
+    }
+    impl SingleChannelProcessor for Delayed {
+        type ParamsBlock = Block;
+        fn new(
+            _block_size: usize,
+            _channel: ChannelInfo,
+            _sample_rate: f32,
+            _params: Arc<ImplementsParams>,
+        ) -> Self {
+            Self {
+                delay: DelayLine::new(3),
+            }
+        }
+        fn process(
+            &mut self,
+            block: &[f32],
+            output: &mut [f32],
+            _params_block: &Self::ParamsBlock,
+        ) -> ProcessStatus {
+            output.copy_from_slice(block);
+            self.delay.process(output);
+            ProcessStatus::Normal
+        }
+        fn latency(&self) -> usize {
+            3
+        }
+    }
+
+    // Makes sure a latency that is not a whole number of host samples is padded, so the reported latency is exact
+
+    #[test]
+    fn test_oversampled_latency() {
+        let (_, _, identity_latency) = process_sine::<Oversampled<Identity, 4>>();
+        let (input, output, latency) = process_sine::<Oversampled<Delayed, 4>>();
+        assert_eq!(identity_latency + 1, latency);
+        for i in 300..1024 {
+            assert!((output[i] - input[i - latency]).abs() < 1e-3_f32);
+        }
+    }
+
+    // Passes the signal through looking ahead by 6 samples, checking the upcoming samples it saw start the next block
+    struct Peek {
+        upcoming: Vec<f32>,
+    }
+    impl SingleChannelProcessor for Peek {
+        type ParamsBlock = Block;
+        fn new(
+            _block_size: usize,
+            _channel: ChannelInfo,
+            _sample_rate: f32,
+            _params: Arc<ImplementsParams>,
+        ) -> Self {
+            Self {
+                upcoming: Vec::with_capacity(6),
+            }
+        }
+        fn process(
+            &mut self,
+            _block: &[f32],
+            _output: &mut [f32],
+            _params_block: &Self::ParamsBlock,
+        ) -> ProcessStatus {
+            ProcessStatus::Error("No lookahead")
+        }
+        fn process_with_context<S: SysExMessage>(
+            &mut self,
+            block: &[f32],
+            output: &mut [f32],
+            _params_block: &Self::ParamsBlock,
+            context: &BlockContext<f32, S>,
+        ) -> ProcessStatus {
+            assert_eq!(6, context.lookahead().len());
+            assert_eq!(self.upcoming, block[0..self.upcoming.len()]);
+            self.upcoming.clear();
+            self.upcoming.extend_from_slice(context.lookahead());
+
+            output.copy_from_slice(block);
+            ProcessStatus::Normal
+        }
+        fn lookahead(&self) -> usize {
+            6
+        }
+    }
+
+    // Makes sure the lookahead is upsampled, and rounded up to whole host samples
+
+    #[test]
+    fn test_oversampled_lookahead() {
+        let (_, _, identity_latency) = process_sine::<Oversampled<Identity, 4>>();
+        let (input, output, latency) = process_sine::<Oversampled<Peek, 4>>();
+        assert_eq!(identity_latency + 2, latency);
+        for i in 300..1024 {
+            assert!((output[i] - input[i - latency]).abs() < 1e-3_f32);
+        }
+    }
+
+    // Outputs the sidechain, checking the params, events and transport follow the oversampled rate
+    struct Sidechain {}
+    impl SingleChannelProcessor for Sidechain {
+        type ParamsBlock = Block;
+        fn new(
+            _block_size: usize,
+            _channel: ChannelInfo,
+            _sample_rate: f32,
+            _params: Arc<ImplementsParams>,
+        ) -> Self {
+            Self {}
+        }
+        fn process(
+            &mut self,
+            _block: &[f32],
+            _output: &mut [f32],
+            _params_block: &Self::ParamsBlock,
+        ) -> ProcessStatus {
+            unreachable!()
+        }
+        fn process_with_context<S: SysExMessage>(
+            &mut self,
+            block: &[f32],
+            output: &mut [f32],
+            params_block: &Self::ParamsBlock,
+            context: &BlockContext<f32, S>,
+        ) -> ProcessStatus {
+            assert_eq!(4_f32 * 1000_f32, context.transport().sample_rate);
+            let timings: Vec<usize> = context.events().map(|event| event.timing).collect();
+            assert_eq!(vec![20], timings);
+            for (i, value) in params_block.ramp.iter().enumerate() {
+                assert_eq!(params_block.ramp[0] + (i / 4) as f32, *value);
+            }
+
+            let sidechain = context.sidechain().unwrap();
+            assert_eq!(block.len(), sidechain.len());
+            output.copy_from_slice(sidechain);
+            ProcessStatus::Normal
+        }
+    }
+
+    // Makes sure the sidechain is upsampled, and events and params are spread over the oversampled block
+
+    #[test]
+    fn test_oversampled_context() {
+        let params = Arc::new(ImplementsParams {});
+        let mut proc: Oversampled<Sidechain, 4> =
+            Oversampled::new(32, ChannelInfo::new(0, 1), 1000_f32, params.clone());
+        proc.set_aux_inputs(&[1]);
+        let mut params_block = super::OversampledBlock::<Block, 4>::new(params, 32);
+        let events = vec![BlockEvent {
+            timing: 5,
+            event: NoteEvent::<()>::NoteOn {
+                timing: 5,
+                voice_id: None,
+                channel: 0,
+                note: 60,
+                velocity: 1_f32,
+            },
+        }];
+        let transport = TransportInfo {
+            sample_rate: 1000_f32,
+            ..TransportInfo::default()
+        };
+
+        let sidechain: Vec<f32> = (0..1024)
+            .map(|i| (2_f32 * std::f32::consts::PI * 0.02_f32 * i as f32).sin())
+            .collect();
+        let mut output = vec![0_f32; 1024];
+        for (aux_block, output) in sidechain.chunks(32).zip(output.chunks_mut(32)) {
+            let aux_inputs = vec![vec![aux_block.to_vec()]];
+            params_block.from_params();
+            proc.process_with_context(
+                &[0_f32; 32],
+                output,
+                &params_block,
+                &BlockContext::new(&aux_inputs, &events, transport),
+            );
+        }
+
+        let latency = proc.latency();
+        for i in 300..1024 {
+            assert!((output[i] - sidechain[i - latency]).abs() < 1e-3_f32);
+        }
+    }
+}
@@ -77,6 +77,15 @@ fn expand_border(val: f32, min: bool) -> f32 {
     }
 }
 
+// Gets RMS/Peak data of the channels
+fn get_value(channels: &[&mut [f32]], plot_type: PlotType) -> f32 {
+    match plot_type {
+        PlotType::Rms => get_rms(channels),
+        PlotType::Peak => get_peak(channels),
+        PlotType::MeanLoudness => get_mean_loudness(channels),
+    }
+}
+
 // Gets points data by modulating a parameter and collecting RMS/Peak data
 fn get_points<SCP>(
     mut proc: DspCoreProcessor<SCP>,
@@ -94,12 +103,7 @@ where
     let (mut a, mut b) = read_data(audio_path, SAMPLE_RATE * 30, SAMPLES);
     println!("File reading time: {:?}", read_file_instant.elapsed());
 
-    let mut buf = buffer_from_vec(&mut a, &mut b);
-    let start_val = match plot_type {
-        PlotType::Rms => get_rms(&buf),
-        PlotType::Peak => get_peak(&buf),
-        PlotType::MeanLoudness => get_mean_loudness(&mut buf),
-    };
+    let start_val = get_value(&[&mut a[..], &mut b[..]], plot_type);
 
     let mut param_change_duration = Duration::new(0, 0);
     let mut copy_data_duration = Duration::new(0, 0);
@@ -126,21 +130,14 @@ where
         a1.copy_from_slice(&a);
         b1.copy_from_slice(&b);
 
-        let mut buf = buffer_from_vec(&mut a1, &mut b1);
+        let channels = &mut [&mut a1[..], &mut b1[..]];
         copy_data_duration += t.elapsed();
 
         let t = Instant::now();
-        proc.process(&mut buf);
+        proc.process_slices(channels);
         process_duration += t.elapsed();
 
-        points[i] = (
-            val,
-            match plot_type {
-                PlotType::Rms => get_rms(&buf),
-                PlotType::Peak => get_peak(&buf),
-                PlotType::MeanLoudness => get_mean_loudness(&mut buf),
-            } / start_val,
-        );
+        points[i] = (val, get_value(channels, plot_type) / start_val);
     }
 
     println!("Param change time: {:?}", param_change_duration);
@@ -228,7 +225,8 @@ pub fn plot<SCP>(
 ) where
     SCP: SingleChannelProcessor,
 {
-    let mut proc: DspCoreProcessor<SCP> = DspCoreProcessor::new(params, block_size, 2);
+    let mut proc: DspCoreProcessor<SCP> =
+        DspCoreProcessor::new(params, block_size, 2, SAMPLE_RATE as f32);
     zero_params(&mut proc.params_block);
 
     let mut points = get_points::<SCP>(proc, change_param, param_data, audio_path, plot_type);
@@ -0,0 +1,480 @@
+use std::fmt;
+use std::fmt::Display;
+use std::path::Path;
+use std::sync::Arc;
+
+use hound::SampleFormat;
+use hound::WavReader;
+use hound::WavSpec;
+use hound::WavWriter;
+use nih_plug::prelude::ProcessStatus;
+use nih_plug::prelude::SysExMessage;
+
+use crate::BlockContext;
+use crate::ChannelInfo;
+use crate::DspCoreProcessor;
+use crate::ParamsBlock;
+use crate::ProcessError;
+use crate::SingleChannelProcessor;
+
+/// Settings of [`render`] and [`render_file`]
+#[derive(Clone, Copy, Debug)]
+pub struct RenderConfig {
+    /// `block_size` the processor is created with
+    pub block_size: usize,
+    /// Number of samples per host buffer
+    pub buffer_size: usize,
+    /// Most samples rendered after the end of the input, to keep the [`SingleChannelProcessor::tail`] of the processor
+    ///
+    /// Bounds infinite tails. `0` cuts the output to the length of the input
+    pub max_tail: usize,
+}
+
+impl Default for RenderConfig {
+    fn default() -> Self {
+        Self {
+            block_size: 64,
+            buffer_size: 512,
+            max_tail: 480000,
+        }
+    }
+}
+
+/// Error that stopped a render
+#[derive(Debug)]
+pub enum RenderError {
+    /// Reading or writing the WAV file failed
+    Wav(hound::Error),
+    /// Processing a buffer failed
+    Process(ProcessError),
+    /// The [`RenderConfig`] cannot be rendered with, with the reason
+    InvalidConfig(&'static str),
+}
+
+impl Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Wav(error) => write!(f, "WAV file error: {error}"),
+            Self::Process(error) => write!(f, "Processing failed: {}", error.message()),
+            Self::InvalidConfig(reason) => write!(f, "Invalid render config: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for RenderError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Wav(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<hound::Error> for RenderError {
+    fn from(error: hound::Error) -> Self {
+        Self::Wav(error)
+    }
+}
+
+impl From<ProcessError> for RenderError {
+    fn from(error: ProcessError) -> Self {
+        Self::Process(error)
+    }
+}
+
+/// Run `SCP` over `input`, holding one vector of samples per channel, and return the processed channels.
+///
+/// The channels are processed by a [`DspCoreProcessor`] in host buffers of `config.buffer_size` samples. The reported latency
+/// is trimmed, so the output lines up with the input. It is longer than the input by the [`SingleChannelProcessor::tail`] of
+/// the processor, up to `config.max_tail` samples. `overrides` is called on the [`ParamsBlock`] every time it is filled
+/// from the params, set the params you want to render with there. Without the `test` feature it runs after
+/// [`ParamsBlock::from_params`], so it only has to set the values it changes.
+///
+/// Fails with [`RenderError::InvalidConfig`] if `config.block_size` or `config.buffer_size` is `0`.
+pub fn render<SCP: SingleChannelProcessor>(
+    params: Arc<<SCP::ParamsBlock as ParamsBlock>::Params>,
+    overrides: impl Fn(&mut SCP::ParamsBlock) + 'static,
+    input: &[Vec<f32>],
+    sample_rate: f32,
+    config: &RenderConfig,
+) -> Result<Vec<Vec<f32>>, RenderError> {
+    if config.block_size == 0 {
+        return Err(RenderError::InvalidConfig("block_size is 0"));
+    }
+    if config.buffer_size == 0 {
+        return Err(RenderError::InvalidConfig("buffer_size is 0"));
+    }
+
+    let mut proc: DspCoreProcessor<Overridden<SCP>> =
+        DspCoreProcessor::new(params, config.block_size, input.len(), sample_rate);
+    overrides(&mut proc.params_block.block);
+    proc.params_block.overrides = Box::new(overrides);
+
+    // Flush the latency and the tail out with silence, and drop the latency from the start
+    let latency = proc.latency();
+    let tail = proc.tail().saturating_sub(latency).min(config.max_tail);
+    let samples = input.first().map_or(0, |channel| channel.len()) + tail;
+    let mut output: Vec<Vec<f32>> = input
+        .iter()
+        .map(|channel| {
+            let mut channel = channel.clone();
+            channel.resize(samples + latency, 0_f32);
+            channel
+        })
+        .collect();
+
+    let mut start = 0;
+    while start < samples + latency {
+        let len = config.buffer_size.min(samples + latency - start);
+        let mut slices: Vec<&mut [f32]> = output
+            .iter_mut()
+            .map(|channel| &mut channel[start..start + len])
+            .collect();
+
+        if let ProcessStatus::Error(message) = proc.process_slices(&mut slices) {
+            return Err(proc
+                .last_error()
+                .unwrap_or(ProcessError::Processor(message))
+                .into());
+        }
+        start += len;
+    }
+
+    for channel in output.iter_mut() {
+        channel.drain(0..latency);
+    }
+    Ok(output)
+}
+
+/// Run `SCP` over the WAV file at `input_path`, and write the result to `output_path`.
+///
+/// The file can have any number of channels, sample rate and sample format, the processor is created for its channel count
+/// and sample rate. The output is written as 32 bit float WAV with the same channels and sample rate, see [`render`].
+pub fn render_file<SCP: SingleChannelProcessor>(
+    params: Arc<<SCP::ParamsBlock as ParamsBlock>::Params>,
+    overrides: impl Fn(&mut SCP::ParamsBlock) + 'static,
+    input_path: impl AsRef<Path>,
+    output_path: impl AsRef<Path>,
+    config: &RenderConfig,
+) -> Result<(), RenderError> {
+    let (input, sample_rate) = read_wav(input_path)?;
+    let output = render::<SCP>(params, overrides, &input, sample_rate as f32, config)?;
+    write_wav(output_path, &output, sample_rate)?;
+    Ok(())
+}
+
+/// Read the WAV file at `path`, returning one vector of samples per channel and the sample rate.
+///
+/// Integer samples are scaled to `-1.0..1.0`.
+pub fn read_wav(path: impl AsRef<Path>) -> Result<(Vec<Vec<f32>>, u32), hound::Error> {
+    let mut reader = WavReader::open(path)?;
+    let spec = reader.spec();
+    let channels = spec.channels as usize;
+
+    let samples: Vec<f32> = match spec.sample_format {
+        SampleFormat::Float => reader.samples::<f32>().collect::<Result<_, _>>()?,
+        SampleFormat::Int => {
+            let scale = (1_i64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .map(|sample| sample.map(|sample| sample as f32 / scale))
+                .collect::<Result<_, _>>()?
+        }
+    };
+
+    let mut output = vec![Vec::with_capacity(samples.len() / channels.max(1)); channels];
+    for frame in samples.chunks_exact(channels) {
+        for (channel, &sample) in output.iter_mut().zip(frame) {
+            channel.push(sample);
+        }
+    }
+    Ok((output, spec.sample_rate))
+}
+
+/// Write `channels`, holding one vector of samples per channel, to a 32 bit float WAV file at `path`.
+pub fn write_wav(
+    path: impl AsRef<Path>,
+    channels: &[Vec<f32>],
+    sample_rate: u32,
+) -> Result<(), hound::Error> {
+    let spec = WavSpec {
+        channels: channels.len() as u16,
+        sample_rate,
+        bits_per_sample: 32,
+        sample_format: SampleFormat::Float,
+    };
+    let mut writer = WavWriter::create(path, spec)?;
+
+    let samples = channels.first().map_or(0, |channel| channel.len());
+    for index in 0..samples {
+        for channel in channels {
+            writer.write_sample(channel[index])?;
+        }
+    }
+    writer.finalize()
+}
+
+// Params block of the rendered processor, applying the overrides every time it is filled
+struct OverriddenBlock<B> {
+    block: B,
+    overrides: Box<dyn Fn(&mut B)>,
+}
+
+impl<B: ParamsBlock> ParamsBlock for OverriddenBlock<B> {
+    type Params = B::Params;
+
+    fn new(params: Arc<Self::Params>, block_size: usize) -> Self {
+        Self {
+            block: B::new(params, block_size),
+            overrides: Box::new(|_| {}),
+        }
+    }
+
+    fn from_params(&mut self) {
+        self.block.from_params();
+        (self.overrides)(&mut self.block);
+    }
+
+    fn partial_from_params(&mut self, block_len: usize) {
+        self.block.partial_from_params(block_len);
+        (self.overrides)(&mut self.block);
+    }
+}
+
+// Wraps the rendered processor, passing it the overridden params block
+struct Overridden<SCP> {
+    processor: SCP,
+}
+
+impl<SCP: SingleChannelProcessor> SingleChannelProcessor for Overridden<SCP> {
+    type ParamsBlock = OverriddenBlock<SCP::ParamsBlock>;
+
+    fn new(
+        block_size: usize,
+        channel: ChannelInfo,
+        sample_rate: f32,
+        params: Arc<<Self::ParamsBlock as ParamsBlock>::Params>,
+    ) -> Self {
+        Self {
+            processor: SCP::new(block_size, channel, sample_rate, params),
+        }
+    }
+
+    fn process(
+        &mut self,
+        block: &[f32],
+        output: &mut [f32],
+        params_block: &Self::ParamsBlock,
+    ) -> ProcessStatus {
+        self.processor.process(block, output, &params_block.block)
+    }
+
+    fn process_with_context<S: SysExMessage>(
+        &mut self,
+        block: &[f32],
+        output: &mut [f32],
+        params_block: &Self::ParamsBlock,
+        context: &BlockContext<f32, S>,
+    ) -> ProcessStatus {
+        self.processor
+            .process_with_context(block, output, &params_block.block, context)
+    }
+
+    fn try_process<S: SysExMessage>(
+        &mut self,
+        block: &[f32],
+        output: &mut [f32],
+        params_block: &Self::ParamsBlock,
+        context: &BlockContext<f32, S>,
+    ) -> Result<(), ProcessError> {
+        self.processor
+            .try_process(block, output, &params_block.block, context)
+    }
+
+    fn latency(&self) -> usize {
+        self.processor.latency()
+    }
+
+    fn lookahead(&self) -> usize {
+        self.processor.lookahead()
+    }
+
+    fn tail(&self) -> usize {
+        self.processor.tail()
+    }
+
+    fn accepts_variable_block_size(&self) -> bool {
+        self.processor.accepts_variable_block_size()
+    }
+
+    fn reset(&mut self) {
+        self.processor.reset();
+    }
+
+    fn suspend(&mut self) {
+        self.processor.suspend();
+    }
+
+    fn set_aux_inputs(&mut self, aux_input_ports: &[usize]) {
+        self.processor.set_aux_inputs(aux_input_ports);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use nih_plug::params::Params;
+    use nih_plug::prelude::ProcessStatus;
+
+    use super::{read_wav, render, write_wav, RenderConfig, RenderError};
+    use crate::delay::DelayLine;
+    use crate::{ChannelInfo, ParamsBlock, SingleChannelProcessor};
+
+    #[derive(Params)]
+    struct ImplementsParams {}
+    struct Block {
+        gain: Vec<f32>,
+    }
+    impl ParamsBlock for Block {
+        type Params = ImplementsParams;
+        fn new(_params: Arc<Self::Params>, block_size: usize) -> Self {
+            Self {
+                gain: vec![1_f32; block_size],
+            }
+        }
+        fn from_params(&mut self) {
+            self.gain.fill(1_f32);
+        }
+    }
+
+    // Applies the gain and delays by 5 samples, reporting it, and a tail of 10 samples
+    struct DelayedGain {
+        delay: DelayLine<f32>,
+    }
+    impl SingleChannelProcessor for DelayedGain {
+        type ParamsBlock = Block;
+        fn new(
+            _block_size: usize,
+            _channel: ChannelInfo,
+            _sample_rate: f32,
+            _params: Arc<ImplementsParams>,
+        ) -> Self {
+            Self {
+                delay: DelayLine::new(5),
+            }
+        }
+        fn process(
+            &mut self,
+            block: &[f32],
+            output: &mut [f32],
+            params_block: &Self::ParamsBlock,
+        ) -> ProcessStatus {
+            for ((output, sample), gain) in output.iter_mut().zip(block).zip(&params_block.gain) {
+                *output = sample * gain;
+            }
+            self.delay.process(output);
+            ProcessStatus::Normal
+        }
+        fn latency(&self) -> usize {
+            5
+        }
+        fn tail(&self) -> usize {
+            10
+        }
+    }
+
+    // Makes sure the output lines up with the input, with the overridden params, and keeps the tail
+
+    #[test]
+    fn test_render() {
+        let input: Vec<Vec<f32>> = (0..3)
+            .map(|channel| (0..1000).map(|i| (i * (channel + 1)) as f32).collect())
+            .collect();
+        let config = RenderConfig {
+            block_size: 16,
+            buffer_size: 100,
+            ..RenderConfig::default()
+        };
+
+        for gain in [0.5_f32, 0.25_f32] {
+            let output = render::<DelayedGain>(
+                Arc::new(ImplementsParams {}),
+                move |params_block| params_block.gain.fill(gain),
+                &input,
+                48000_f32,
+                &config,
+            )
+            .unwrap();
+
+            for (input, output) in input.iter().zip(&output) {
+                let mut expected: Vec<f32> = input.iter().map(|sample| sample * gain).collect();
+                expected.resize(1010, 0_f32);
+                assert_eq!(expected, *output);
+            }
+        }
+    }
+
+    // Makes sure empty blocks and buffers are rejected instead of never finishing
+
+    #[test]
+    fn test_invalid_config() {
+        let input = vec![vec![1_f32; 100]];
+        for config in [
+            RenderConfig {
+                block_size: 0,
+                ..RenderConfig::default()
+            },
+            RenderConfig {
+                buffer_size: 0,
+                ..RenderConfig::default()
+            },
+        ] {
+            let result = render::<DelayedGain>(
+                Arc::new(ImplementsParams {}),
+                |_| {},
+                &input,
+                48000_f32,
+                &config,
+            );
+            assert!(matches!(result, Err(RenderError::InvalidConfig(_))));
+        }
+    }
+
+    // Makes sure the tail is cut at max_tail
+
+    #[test]
+    fn test_max_tail() {
+        let input = vec![vec![1_f32; 100]];
+        let config = RenderConfig {
+            max_tail: 4,
+            ..RenderConfig::default()
+        };
+
+        let output = render::<DelayedGain>(
+            Arc::new(ImplementsParams {}),
+            |_| {},
+            &input,
+            48000_f32,
+            &config,
+        )
+        .unwrap();
+        assert_eq!(104, output[0].len());
+    }
+
+    #[test]
+    fn test_wav() {
+        let channels = vec![
+            vec![0.25_f32, -0.5_f32, 1_f32],
+            vec![0_f32, 0.75_f32, -1_f32],
+        ];
+        let path = std::env::temp_dir().join("dsp-utils-test-wav.wav");
+
+        write_wav(&path, &channels, 96000).unwrap();
+        let (read, sample_rate) = read_wav(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(channels, read);
+        assert_eq!(96000, sample_rate);
+    }
+}
@@ -0,0 +1,165 @@
+use std::fmt::Debug;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+/// Floating point type that samples are processed in
+///
+/// Implemented for `f32` and `f64`. Hosts always exchange `f32` samples, [`crate::LinkedDspCoreProcessor`] converts them
+/// from and to the sample type of its processor. Use `f64` for processing that accumulates rounding errors, like high Q
+/// filters or long feedback networks.
+pub trait Sample:
+    Copy
+    + Default
+    + PartialEq
+    + PartialOrd
+    + Debug
+    + Send
+    + Sync
+    + 'static
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+    + AddAssign
+    + SubAssign
+    + MulAssign
+    + DivAssign
+    + Sum
+{
+    /// `0.0`
+    const ZERO: Self;
+    /// `1.0`
+    const ONE: Self;
+    /// Archimedes' constant
+    const PI: Self;
+
+    /// Convert from `f32`, which is what the host uses
+    fn from_f32(value: f32) -> Self;
+    /// Convert into `f32`, which is what the host uses
+    fn to_f32(self) -> f32;
+    /// Convert from `f64`, rounding if needed
+    fn from_f64(value: f64) -> Self;
+    /// Convert into `f64`
+    fn to_f64(self) -> f64;
+
+    /// Absolute value
+    fn abs(self) -> Self;
+    /// Square root
+    fn sqrt(self) -> Self;
+    /// Sine, in radians
+    fn sin(self) -> Self;
+    /// Cosine, in radians
+    fn cos(self) -> Self;
+    /// Tangent, in radians
+    fn tan(self) -> Self;
+    /// Four quadrant arctangent of `self` (y) and `other` (x), in radians
+    fn atan2(self, other: Self) -> Self;
+    /// `e^self`
+    fn exp(self) -> Self;
+    /// Natural logarithm
+    fn ln(self) -> Self;
+    /// Integer power
+    fn powi(self, n: i32) -> Self;
+    /// `self * a + b`, with only one rounding error
+    fn mul_add(self, a: Self, b: Self) -> Self;
+    /// Maximum of two numbers
+    fn max(self, other: Self) -> Self;
+    /// Minimum of two numbers
+    fn min(self, other: Self) -> Self;
+}
+
+macro_rules! impl_sample {
+    ($t:ident) => {
+        impl Sample for $t {
+            const ZERO: Self = 0.0;
+            const ONE: Self = 1.0;
+            const PI: Self = std::$t::consts::PI;
+
+            #[inline]
+            fn from_f32(value: f32) -> Self {
+                value as $t
+            }
+            #[inline]
+            fn to_f32(self) -> f32 {
+                self as f32
+            }
+            #[inline]
+            fn from_f64(value: f64) -> Self {
+                value as $t
+            }
+            #[inline]
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+
+            #[inline]
+            fn abs(self) -> Self {
+                $t::abs(self)
+            }
+            #[inline]
+            fn sqrt(self) -> Self {
+                $t::sqrt(self)
+            }
+            #[inline]
+            fn sin(self) -> Self {
+                $t::sin(self)
+            }
+            #[inline]
+            fn cos(self) -> Self {
+                $t::cos(self)
+            }
+            #[inline]
+            fn tan(self) -> Self {
+                $t::tan(self)
+            }
+            #[inline]
+            fn atan2(self, other: Self) -> Self {
+                $t::atan2(self, other)
+            }
+            #[inline]
+            fn exp(self) -> Self {
+                $t::exp(self)
+            }
+            #[inline]
+            fn ln(self) -> Self {
+                $t::ln(self)
+            }
+            #[inline]
+            fn powi(self, n: i32) -> Self {
+                $t::powi(self, n)
+            }
+            #[inline]
+            fn mul_add(self, a: Self, b: Self) -> Self {
+                $t::mul_add(self, a, b)
+            }
+            #[inline]
+            fn max(self, other: Self) -> Self {
+                $t::max(self, other)
+            }
+            #[inline]
+            fn min(self, other: Self) -> Self {
+                $t::min(self, other)
+            }
+        }
+    };
+}
+
+impl_sample!(f32);
+impl_sample!(f64);
+
+/// Copy host samples into a block of any [`Sample`] type
+#[inline]
+pub fn read_samples<T: Sample>(block: &mut [T], host: &[f32]) {
+    for (sample, &host) in block.iter_mut().zip(host) {
+        *sample = T::from_f32(host);
+    }
+}
+
+/// Copy a block of any [`Sample`] type into host samples
+#[inline]
+pub fn write_samples<T: Sample>(host: &mut [f32], block: &[T]) {
+    for (host, &sample) in host.iter_mut().zip(block) {
+        *host = sample.to_f32();
+    }
+}
@@ -0,0 +1,263 @@
+use std::cell::UnsafeCell;
+use std::mem::MaybeUninit;
+use std::sync::atomic::AtomicU32;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+/// `f32` that can be shared between threads, e.g. a peak level or gain reduction for the editor
+///
+/// Stores and loads are single atomic operations on the bits of the value, so they never block and are safe to call from
+/// `process`.
+#[derive(Default, Debug)]
+pub struct AtomicF32(AtomicU32);
+
+impl AtomicF32 {
+    /// Create a cell holding `value`
+    pub fn new(value: f32) -> Self {
+        Self(AtomicU32::new(value.to_bits()))
+    }
+
+    /// The current value
+    pub fn load(&self) -> f32 {
+        f32::from_bits(self.0.load(Ordering::Relaxed))
+    }
+
+    /// Replace the value with `value`
+    pub fn store(&self, value: f32) {
+        self.0.store(value.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Replace the value with `value`, returning the previous one
+    ///
+    /// Lets the editor take the peak collected since its last frame, e.g. `swap(0_f32)`.
+    pub fn swap(&self, value: f32) -> f32 {
+        f32::from_bits(self.0.swap(value.to_bits(), Ordering::Relaxed))
+    }
+
+    /// Replace the value with `value` if it is bigger, returning the previous one
+    pub fn fetch_max(&self, value: f32) -> f32 {
+        let previous = self
+            .0
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+                (value > f32::from_bits(bits)).then_some(value.to_bits())
+            })
+            .unwrap_or_else(|bits| bits);
+        f32::from_bits(previous)
+    }
+}
+
+/// Create a ring buffer holding up to `capacity` values, returning its two ends.
+///
+/// Give the [`Producer`] to the processor and the [`Consumer`] to the editor. Pushing and popping never block or allocate, and
+/// finish in a bounded number of steps, so both ends can be used from realtime threads. Use it to send sample snapshots for
+/// scopes, or any stream of values the editor should not miss.
+pub fn ring_buffer<T: Copy + Send>(capacity: usize) -> (Producer<T>, Consumer<T>) {
+    assert!(capacity > 0, "Ring buffer capacity cannot be zero");
+    let shared = Arc::new(Shared {
+        buffer: (0..capacity)
+            .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+            .collect(),
+        head: AtomicUsize::new(0),
+        tail: AtomicUsize::new(0),
+    });
+    (
+        Producer {
+            shared: shared.clone(),
+        },
+        Consumer { shared },
+    )
+}
+
+// Storage of the ring buffer. `head` and `tail` only grow, wrapping around, and are taken modulo the capacity to get the slot
+struct Shared<T> {
+    buffer: Box<[UnsafeCell<MaybeUninit<T>>]>,
+    // Next slot to read, only written by the consumer
+    head: AtomicUsize,
+    // Next slot to write, only written by the producer
+    tail: AtomicUsize,
+}
+
+// The producer only writes slots the consumer is done with, and the consumer only reads slots the producer published
+unsafe impl<T: Send> Sync for Shared<T> {}
+
+impl<T> Shared<T> {
+    fn capacity(&self) -> usize {
+        self.buffer.len()
+    }
+
+    fn len(&self) -> usize {
+        self.tail
+            .load(Ordering::Acquire)
+            .wrapping_sub(self.head.load(Ordering::Acquire))
+    }
+
+    fn slot(&self, index: usize) -> *mut MaybeUninit<T> {
+        self.buffer[index % self.capacity()].get()
+    }
+}
+
+/// Writing end of a [`ring_buffer`]
+pub struct Producer<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T: Copy> Producer<T> {
+    /// Push `value`, returning `false` without pushing it if the buffer is full
+    pub fn push(&mut self, value: T) -> bool {
+        self.push_slice(&[value]) == 1
+    }
+
+    /// Push as many of `values` as fit, returning how many were pushed
+    pub fn push_slice(&mut self, values: &[T]) -> usize {
+        let tail = self.shared.tail.load(Ordering::Relaxed);
+        let head = self.shared.head.load(Ordering::Acquire);
+        let len = values
+            .len()
+            .min(self.shared.capacity() - tail.wrapping_sub(head));
+
+        for (offset, &value) in values[..len].iter().enumerate() {
+            // The slot is free, the consumer moved past it
+            unsafe { (*self.shared.slot(tail.wrapping_add(offset))).write(value) };
+        }
+        self.shared
+            .tail
+            .store(tail.wrapping_add(len), Ordering::Release);
+        len
+    }
+
+    /// Number of values waiting to be popped
+    pub fn len(&self) -> usize {
+        self.shared.len()
+    }
+
+    /// Whether there are no values waiting to be popped
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of values the buffer holds
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity()
+    }
+}
+
+/// Reading end of a [`ring_buffer`]
+pub struct Consumer<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T: Copy> Consumer<T> {
+    /// Pop the oldest value, if there is one
+    pub fn pop(&mut self) -> Option<T> {
+        let mut output = None;
+        self.pop_with(1, |_, value| output = Some(value));
+        output
+    }
+
+    /// Pop the oldest values into `output`, returning how many were popped
+    pub fn pop_slice(&mut self, output: &mut [T]) -> usize {
+        self.pop_with(output.len(), |offset, value| output[offset] = value)
+    }
+
+    /// Drop all values waiting to be popped
+    pub fn clear(&mut self) {
+        let tail = self.shared.tail.load(Ordering::Acquire);
+        self.shared.head.store(tail, Ordering::Release);
+    }
+
+    /// Number of values waiting to be popped
+    pub fn len(&self) -> usize {
+        self.shared.len()
+    }
+
+    /// Whether there are no values waiting to be popped
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of values the buffer holds
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity()
+    }
+
+    // Pops up to `max` of the oldest values, passing them to `f` with their offset, and returns how many were popped
+    fn pop_with(&mut self, max: usize, mut f: impl FnMut(usize, T)) -> usize {
+        let head = self.shared.head.load(Ordering::Relaxed);
+        let tail = self.shared.tail.load(Ordering::Acquire);
+        let len = max.min(tail.wrapping_sub(head));
+
+        for offset in 0..len {
+            // The slot was written, the producer moved past it
+            f(offset, unsafe {
+                (*self.shared.slot(head.wrapping_add(offset))).assume_init()
+            });
+        }
+        self.shared
+            .head
+            .store(head.wrapping_add(len), Ordering::Release);
+        len
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ring_buffer, AtomicF32};
+
+    #[test]
+    fn test_atomic_f32() {
+        let cell = AtomicF32::new(0.5_f32);
+        assert_eq!(0.5_f32, cell.fetch_max(0.25_f32));
+        assert_eq!(0.5_f32, cell.fetch_max(2_f32));
+        assert_eq!(2_f32, cell.swap(0_f32));
+        cell.store(-1_f32);
+        assert_eq!(-1_f32, cell.load());
+    }
+
+    #[test]
+    fn test_ring_buffer() {
+        let (mut producer, mut consumer) = ring_buffer::<f32>(4);
+        assert_eq!(None, consumer.pop());
+
+        assert_eq!(3, producer.push_slice(&[1_f32, 2_f32, 3_f32]));
+        assert_eq!(Some(1_f32), consumer.pop());
+        // Wraps around, dropping what does not fit
+        assert_eq!(2, producer.push_slice(&[4_f32, 5_f32, 6_f32]));
+        assert!(!producer.push(7_f32));
+        assert_eq!(4, consumer.len());
+
+        let mut output = [0_f32; 3];
+        assert_eq!(3, consumer.pop_slice(&mut output));
+        assert_eq!([2_f32, 3_f32, 4_f32], output);
+        assert_eq!(Some(5_f32), consumer.pop());
+        assert!(consumer.is_empty());
+
+        producer.push(8_f32);
+        consumer.clear();
+        assert_eq!(None, consumer.pop());
+    }
+
+    // Makes sure values arrive in order when both ends run at once
+
+    #[test]
+    fn test_threads() {
+        let (mut producer, mut consumer) = ring_buffer::<usize>(64);
+        let thread = std::thread::spawn(move || {
+            let mut value = 0;
+            while value < 10000 {
+                if producer.push(value) {
+                    value += 1;
+                }
+            }
+        });
+
+        let mut expected = 0;
+        while expected < 10000 {
+            if let Some(value) = consumer.pop() {
+                assert_eq!(expected, value);
+                expected += 1;
+            }
+        }
+        thread.join().unwrap();
+    }
+}
@@ -0,0 +1,483 @@
+use std::sync::Arc;
+
+use assert_no_alloc::assert_no_alloc;
+#[cfg(all(debug_assertions, feature = "testing_alloc_warnings"))]
+use assert_no_alloc::reset_violation_count;
+#[cfg(all(debug_assertions, feature = "testing_alloc_warnings"))]
+use assert_no_alloc::violation_count;
+use nih_plug::buffer::Buffer;
+use nih_plug::prelude::ProcessStatus;
+use nih_plug::prelude::SysExMessage;
+
+use crate::error::to_status;
+use crate::BlockContext;
+use crate::ChannelInfo;
+use crate::DspCoreProcessor;
+use crate::LatencyMode;
+use crate::MultiChannelProcessor;
+use crate::ParamsBlock;
+use crate::ProcessError;
+use crate::Sample;
+use crate::SingleChannelProcessor;
+
+const SAMPLE_RATES: [f32; 4] = [44100_f32, 48000_f32, 88200_f32, 96000_f32];
+
+/// Settings of [`test_processor`]
+#[derive(Clone, Copy, Debug)]
+pub struct TestConfig {
+    /// `block_size` the processor is created with
+    pub block_size: usize,
+    /// Number of runs, each with a new random channel count, sample rate and input signal
+    pub runs: usize,
+    /// Length of the input signal of each run, in samples
+    pub samples: usize,
+    /// Largest host buffer size, the buffer sizes are random between `1` and this
+    pub max_buffer_size: usize,
+    /// Largest number of channels, the channel count is random between `1` and this
+    pub max_channels: usize,
+    /// Seed of the random values, change it to test with other values
+    pub seed: u64,
+    /// Whether to check that an impulse comes out peaking after exactly the reported latency.
+    ///
+    /// This holds for processors that delay the signal without smearing it in time, e.g. lookahead limiters or linear phase
+    /// filters. Disable it for processors that don't pass an impulse through, like gates or reverbs.
+    pub check_latency: bool,
+}
+
+impl Default for TestConfig {
+    fn default() -> Self {
+        Self {
+            block_size: 64,
+            runs: 8,
+            samples: 4096,
+            max_buffer_size: 1024,
+            max_channels: 2,
+            seed: 1,
+            check_latency: true,
+        }
+    }
+}
+
+/// Drive `SCP` through a [`DspCoreProcessor`] the way hosts do, and panic if it misbehaves.
+///
+/// Every run picks a random channel count and sample rate, and processes a random signal split into random host buffer
+/// sizes. Runs alternate between [`LatencyMode::Buffered`] and [`LatencyMode::ZeroLatency`], the latter with buffer sizes
+/// the processor can handle in place, multiples of `block_size` unless it accepts variable block sizes. It checks that:
+/// - every block has `block_size` samples, or at most that many if [`SingleChannelProcessor::accepts_variable_block_size`]
+/// - processing does not allocate, including the framework itself, using [`assert_no_alloc`]
+/// - the output is finite, and no [`ProcessStatus::Error`] is returned
+/// - the output is the same for a second processor given other host buffer sizes, so it is deterministic
+/// - the output is the same after [`crate::LinkedDspCoreProcessor::reset`]
+/// - the latency is sample exact, see [`TestConfig::check_latency`]
+/// - [`LatencyMode::ZeroLatency`] does not fall back to [`LatencyMode::Buffered`]
+///
+/// The allocation check needs the global allocator of nih_plug, which is only set in debug builds with its
+/// `assert_process_allocs` feature, so it is skipped in release builds. An allocation aborts the test process, unless the
+/// `testing_alloc_warnings` feature is enabled, which counts allocations and reports them with a panic. That feature is
+/// shared with nih_plug, so only enable it in test builds: it makes the allocation checks of nih_plug print a warning
+/// instead of aborting. Parameters are read like while processing, so they should not be smoothing.
+///
+/// # Examples
+///
+/// ```no_run
+/// #[test]
+/// fn test_my_processor() {
+///     dsp_utils::testing::test_processor::<MyProcessor, f32>(
+///         Arc::new(MyParams::default()),
+///         TestConfig::default(),
+///     );
+/// }
+/// ```
+pub fn test_processor<SCP, T>(
+    params: Arc<<SCP::ParamsBlock as ParamsBlock>::Params>,
+    config: TestConfig,
+) where
+    SCP: SingleChannelProcessor<T>,
+    T: Sample,
+{
+    let mut random = Random::new(config.seed);
+
+    for run in 0..config.runs {
+        let latency_mode = if run % 2 == 0 {
+            LatencyMode::Buffered
+        } else {
+            LatencyMode::ZeroLatency
+        };
+        let channels = random.range(1, config.max_channels);
+        let sample_rate = SAMPLE_RATES[random.range(0, SAMPLE_RATES.len() - 1)];
+        let new_proc = || -> DspCoreProcessor<Checked<SCP>, T> {
+            DspCoreProcessor::new(params.clone(), config.block_size, channels, sample_rate)
+                .with_latency_mode(latency_mode)
+        };
+
+        let mut proc = new_proc();
+        let buffers = HostBuffers {
+            max_size: config.max_buffer_size,
+            step: match latency_mode {
+                LatencyMode::ZeroLatency if !proc.processor().accepts_variable_block_size() => {
+                    config.block_size
+                }
+                _ => 1,
+            },
+        };
+        let input: Vec<Vec<f32>> = (0..channels)
+            .map(|_| {
+                (0..config.samples.next_multiple_of(buffers.step))
+                    .map(|_| random.signal())
+                    .collect()
+            })
+            .collect();
+        let output = process(&mut proc, &input, &mut random, buffers);
+
+        let mut other = new_proc();
+        assert!(
+            output == process(&mut other, &input, &mut random, buffers),
+            "Output differs between processors, or depends on the host buffer sizes"
+        );
+
+        proc.reset();
+        assert!(
+            output == process(&mut proc, &input, &mut random, buffers),
+            "Output differs after reset"
+        );
+
+        if config.check_latency {
+            check_latency(&mut proc, channels, &mut random, buffers);
+        }
+        assert_eq!(
+            latency_mode,
+            proc.latency_mode(),
+            "Fell back to LatencyMode::Buffered"
+        );
+    }
+}
+
+// Sizes of the host buffers, random multiples of `step` up to `max_size`
+#[derive(Clone, Copy)]
+struct HostBuffers {
+    max_size: usize,
+    step: usize,
+}
+
+/// Run `f` with a [`Buffer`] over `channels`, which must all have the same length.
+///
+/// Saves building the [`Buffer`] by hand when testing.
+pub fn with_buffer<R>(channels: &mut [&mut [f32]], f: impl FnOnce(&mut Buffer) -> R) -> R {
+    let samples = channels.first().map_or(0, |channel| channel.len());
+    assert!(
+        channels.iter().all(|channel| channel.len() == samples),
+        "Channels differ in length"
+    );
+
+    let mut buffer = Buffer::default();
+    // The slices are borrowed for as long as the buffer lives, and all hold `samples` samples
+    unsafe {
+        buffer.set_slices(samples, |slices| {
+            slices.extend(channels.iter_mut().map(|channel| &mut **channel));
+        })
+    }
+    f(&mut buffer)
+}
+
+// Processes `input` split into random host buffer sizes, checking the status and output of every buffer
+fn process<SCP: SingleChannelProcessor<T>, T: Sample>(
+    proc: &mut DspCoreProcessor<Checked<SCP>, T>,
+    input: &[Vec<f32>],
+    random: &mut Random,
+    buffers: HostBuffers,
+) -> Vec<Vec<f32>> {
+    let mut output = input.to_vec();
+    let samples = input[0].len();
+
+    let mut start = 0;
+    while start < samples {
+        let steps = random.range(1, (buffers.max_size / buffers.step).max(1));
+        let len = (steps * buffers.step).min(samples - start);
+        let mut slices: Vec<&mut [f32]> = output
+            .iter_mut()
+            .map(|channel| &mut channel[start..start + len])
+            .collect();
+
+        #[cfg(all(debug_assertions, feature = "testing_alloc_warnings"))]
+        reset_violation_count();
+        let status = with_buffer(&mut slices, |buffer| {
+            assert_no_alloc(|| proc.process(buffer))
+        });
+        #[cfg(all(debug_assertions, feature = "testing_alloc_warnings"))]
+        assert!(violation_count() == 0, "Processing allocated memory");
+        if let ProcessStatus::Error(e) = status {
+            panic!("Processing failed: {}", e);
+        }
+        assert!(
+            slices
+                .iter()
+                .flat_map(|channel| channel.iter())
+                .all(|sample| sample.is_finite()),
+            "Output is not finite"
+        );
+
+        start += len;
+    }
+
+    output
+}
+
+// Processes an impulse on every channel, checking the output peaks at the reported latency
+fn check_latency<SCP: SingleChannelProcessor<T>, T: Sample>(
+    proc: &mut DspCoreProcessor<Checked<SCP>, T>,
+    channels: usize,
+    random: &mut Random,
+    buffers: HostBuffers,
+) {
+    proc.reset();
+    let latency = proc.latency();
+
+    let mut impulse = vec![0_f32; (2 * latency + 1).next_multiple_of(buffers.step)];
+    impulse[0] = 1_f32;
+    let output = process(proc, &vec![impulse; channels], random, buffers);
+
+    for channel in output {
+        let peak = channel
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.abs().total_cmp(&b.abs()))
+            .filter(|(_, sample)| **sample != 0_f32)
+            .map(|(index, _)| index);
+        assert_eq!(
+            Some(latency),
+            peak,
+            "Impulse does not peak at the reported latency"
+        );
+    }
+}
+
+// Wraps the tested processor, checking the blocks it receives
+struct Checked<SCP> {
+    processor: SCP,
+    block_size: usize,
+}
+
+impl<T: Sample, SCP: SingleChannelProcessor<T>> SingleChannelProcessor<T> for Checked<SCP> {
+    type ParamsBlock = SCP::ParamsBlock;
+
+    fn new(
+        block_size: usize,
+        channel: ChannelInfo,
+        sample_rate: f32,
+        params: Arc<<Self::ParamsBlock as ParamsBlock>::Params>,
+    ) -> Self {
+        Self {
+            processor: SCP::new(block_size, channel, sample_rate, params),
+            block_size,
+        }
+    }
+
+    fn process(
+        &mut self,
+        block: &[T],
+        output: &mut [T],
+        params_block: &Self::ParamsBlock,
+    ) -> ProcessStatus {
+        self.process_with_context(
+            block,
+            output,
+            params_block,
+            &BlockContext::<T, ()>::default(),
+        )
+    }
+
+    fn process_with_context<S: SysExMessage>(
+        &mut self,
+        block: &[T],
+        output: &mut [T],
+        params_block: &Self::ParamsBlock,
+        context: &BlockContext<T, S>,
+    ) -> ProcessStatus {
+        to_status(self.try_process(block, output, params_block, context))
+    }
+
+    fn try_process<S: SysExMessage>(
+        &mut self,
+        block: &[T],
+        output: &mut [T],
+        params_block: &Self::ParamsBlock,
+        context: &BlockContext<T, S>,
+    ) -> Result<(), ProcessError> {
+        assert_eq!(block.len(), output.len(), "Output differs in length");
+        if self.processor.accepts_variable_block_size() {
+            assert!(
+                !block.is_empty() && block.len() <= self.block_size,
+                "Block of {} samples",
+                block.len()
+            );
+        } else {
+            assert_eq!(self.block_size, block.len(), "Block of wrong size");
+        }
+
+        self.processor
+            .try_process(block, output, params_block, context)
+    }
+
+    fn latency(&self) -> usize {
+        self.processor.latency()
+    }
+
+    fn lookahead(&self) -> usize {
+        self.processor.lookahead()
+    }
+
+    fn tail(&self) -> usize {
+        self.processor.tail()
+    }
+
+    fn accepts_variable_block_size(&self) -> bool {
+        self.processor.accepts_variable_block_size()
+    }
+
+    fn reset(&mut self) {
+        self.processor.reset();
+    }
+
+    fn suspend(&mut self) {
+        self.processor.suspend();
+    }
+
+    fn set_aux_inputs(&mut self, aux_input_ports: &[usize]) {
+        self.processor.set_aux_inputs(aux_input_ports);
+    }
+}
+
+// Xorshift generator, so runs are reproducible without an extra dependency
+struct Random(u64);
+
+impl Random {
+    fn new(seed: u64) -> Self {
+        // The state must not be zero
+        Self(seed ^ 0x9E37_79B9_7F4A_7C15)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    // Uniform in `min..=max`
+    fn range(&mut self, min: usize, max: usize) -> usize {
+        min + (self.next() % (max - min + 1) as u64) as usize
+    }
+
+    // Uniform in `-1.0..1.0`
+    fn signal(&mut self) -> f32 {
+        (self.next() >> 40) as f32 / (1_u64 << 23) as f32 - 1_f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use nih_plug::params::Params;
+    use nih_plug::prelude::ProcessStatus;
+
+    use super::{test_processor, TestConfig};
+    use crate::delay::DelayLine;
+    use crate::{ChannelInfo, ParamsBlock, SingleChannelProcessor};
+
+    #[derive(Params)]
+    struct ImplementsParams {}
+    struct Block {}
+    impl ParamsBlock for Block {
+        type Params = ImplementsParams;
+        fn new(_params: Arc<Self::Params>, _block_size: usize) -> Self {
+            Self {}
+        }
+        fn from_params(&mut self) {}
+    }
+
+    // Delays the signal by `DELAY` samples, reporting `LATENCY`
+    struct Delay<const DELAY: usize, const LATENCY: usize> {
+        delay: DelayLine<f32>,
+    }
+    impl<const DELAY: usize, const LATENCY: usize> SingleChannelProcessor for Delay<DELAY, LATENCY> {
+        type ParamsBlock = Block;
+        fn new(
+            _block_size: usize,
+            _channel: ChannelInfo,
+            _sample_rate: f32,
+            _params: Arc<ImplementsParams>,
+        ) -> Self {
+            Self {
+                delay: DelayLine::new(DELAY),
+            }
+        }
+        fn process(
+            &mut self,
+            block: &[f32],
+            output: &mut [f32],
+            _params_block: &Self::ParamsBlock,
+        ) -> ProcessStatus {
+            output.copy_from_slice(block);
+            self.delay.process(output);
+            ProcessStatus::Normal
+        }
+        fn latency(&self) -> usize {
+            LATENCY
+        }
+        fn reset(&mut self) {
+            self.delay.reset();
+        }
+    }
+
+    // Collects every block it processed
+    #[cfg(all(debug_assertions, feature = "testing_alloc_warnings"))]
+    struct Allocating {
+        history: Vec<f32>,
+    }
+    #[cfg(all(debug_assertions, feature = "testing_alloc_warnings"))]
+    impl SingleChannelProcessor for Allocating {
+        type ParamsBlock = Block;
+        fn new(
+            _block_size: usize,
+            _channel: ChannelInfo,
+            _sample_rate: f32,
+            _params: Arc<ImplementsParams>,
+        ) -> Self {
+            Self {
+                history: Vec::new(),
+            }
+        }
+        fn process(
+            &mut self,
+            block: &[f32],
+            output: &mut [f32],
+            _params_block: &Self::ParamsBlock,
+        ) -> ProcessStatus {
+            self.history.extend_from_slice(block);
+            output.copy_from_slice(block);
+            ProcessStatus::Normal
+        }
+    }
+
+    #[test]
+    fn test_valid_processor() {
+        test_processor::<Delay<7, 7>, f32>(Arc::new(ImplementsParams {}), TestConfig::default());
+    }
+
+    #[test]
+    #[should_panic(expected = "Impulse does not peak at the reported latency")]
+    fn test_wrong_latency() {
+        test_processor::<Delay<7, 3>, f32>(Arc::new(ImplementsParams {}), TestConfig::default());
+    }
+
+    // Allocations are only detected in debug builds, where nih_plug installs its allocator, and only counted with
+    // testing_alloc_warnings, otherwise they abort
+
+    #[cfg(all(debug_assertions, feature = "testing_alloc_warnings"))]
+    #[test]
+    #[should_panic(expected = "Processing allocated memory")]
+    fn test_allocation() {
+        test_processor::<Allocating, f32>(Arc::new(ImplementsParams {}), TestConfig::default());
+    }
+}
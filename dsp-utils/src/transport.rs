@@ -0,0 +1,172 @@
+use nih_plug::prelude::Transport;
+
+/// Transport and tempo information for the block being processed
+///
+/// A copy of the host's [`Transport`], with the position moved to the first sample of the block. Available through
+/// [`crate::BlockContext::transport`]. Fields the host did not provide are `None`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct TransportInfo {
+    /// Whether the transport is playing
+    pub playing: bool,
+    /// Whether the host is recording
+    pub recording: bool,
+    /// Sample rate, in Hz
+    pub sample_rate: f32,
+    /// Tempo, in beats per minute
+    pub tempo: Option<f64>,
+    /// Upper part of the time signature
+    pub time_sig_numerator: Option<i32>,
+    /// Lower part of the time signature
+    pub time_sig_denominator: Option<i32>,
+    /// Position of the first sample of the block, in samples
+    pub pos_samples: Option<i64>,
+    /// Position of the first sample of the block, in seconds
+    pub pos_seconds: Option<f64>,
+    /// Position of the first sample of the block, in quarter notes
+    pub pos_beats: Option<f64>,
+    /// Position of the start of the bar containing the first sample of the block, in quarter notes
+    pub bar_start_pos_beats: Option<f64>,
+    /// Number of the bar containing the first sample of the block, starting at `0`
+    pub bar_number: Option<i32>,
+}
+
+impl From<&Transport> for TransportInfo {
+    fn from(transport: &Transport) -> Self {
+        Self {
+            playing: transport.playing,
+            recording: transport.recording,
+            sample_rate: transport.sample_rate,
+            tempo: transport.tempo,
+            time_sig_numerator: transport.time_sig_numerator,
+            time_sig_denominator: transport.time_sig_denominator,
+            pos_samples: transport.pos_samples(),
+            pos_seconds: transport.pos_seconds(),
+            pos_beats: transport.pos_beats(),
+            bar_start_pos_beats: transport.bar_start_pos_beats(),
+            bar_number: transport.bar_number(),
+        }
+    }
+}
+
+impl TransportInfo {
+    /// Length of a single beat (quarter note), in samples
+    ///
+    /// Returns `None` if the host did not provide the tempo
+    pub fn samples_per_beat(&self) -> Option<f64> {
+        self.tempo
+            .map(|tempo| 60_f64 / tempo * self.sample_rate as f64)
+    }
+
+    /// Length of a bar, in quarter notes
+    ///
+    /// Returns `None` if the host did not provide the time signature
+    pub fn bar_length_beats(&self) -> Option<f64> {
+        Some(self.time_sig_numerator? as f64 * 4_f64 / self.time_sig_denominator? as f64)
+    }
+
+    /// Moves the position by `samples`, which can be negative. Does nothing if the transport is not playing.
+    ///
+    /// Positions that cannot be derived, like the position in beats without a tempo, become `None`
+    pub(crate) fn advance(&mut self, samples: i64) {
+        if !self.playing || samples == 0 {
+            return;
+        }
+
+        self.pos_samples = self.pos_samples.map(|pos| pos + samples);
+
+        let seconds = samples as f64 / self.sample_rate as f64;
+        self.pos_seconds = self
+            .pos_seconds
+            .filter(|_| self.sample_rate > 0_f32)
+            .map(|pos| pos + seconds);
+
+        let beats = self
+            .samples_per_beat()
+            .map(|length| samples as f64 / length);
+        self.pos_beats = self.pos_beats.zip(beats).map(|(pos, beats)| pos + beats);
+
+        // Moves the bar start by whole bars, until the new position lands in it
+        match (
+            self.pos_beats,
+            self.bar_start_pos_beats,
+            self.bar_number,
+            self.bar_length_beats(),
+        ) {
+            (Some(pos), Some(bar_start), Some(bar_number), Some(bar_length))
+                if bar_length > 0_f64 =>
+            {
+                let bars = ((pos - bar_start) / bar_length).floor();
+                self.bar_start_pos_beats = Some(bar_start + bars * bar_length);
+                self.bar_number = Some(bar_number + bars as i32);
+            }
+            _ => {
+                self.bar_start_pos_beats = None;
+                self.bar_number = None;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TransportInfo;
+
+    fn playing() -> TransportInfo {
+        TransportInfo {
+            playing: true,
+            sample_rate: 48000_f32,
+            tempo: Some(120_f64),
+            time_sig_numerator: Some(3),
+            time_sig_denominator: Some(4),
+            pos_samples: Some(0),
+            pos_seconds: Some(0_f64),
+            pos_beats: Some(0_f64),
+            bar_start_pos_beats: Some(0_f64),
+            bar_number: Some(0),
+            ..Default::default()
+        }
+    }
+
+    // Half a second at 120 BPM is a single beat, bars are 3 beats long
+
+    #[test]
+    fn test_advance() {
+        let mut transport = playing();
+
+        transport.advance(24000);
+        assert_eq!(transport.pos_samples, Some(24000));
+        assert_eq!(transport.pos_seconds, Some(0.5_f64));
+        assert_eq!(transport.pos_beats, Some(1_f64));
+        assert_eq!(transport.bar_start_pos_beats, Some(0_f64));
+        assert_eq!(transport.bar_number, Some(0));
+
+        transport.advance(24000 * 6);
+        assert_eq!(transport.pos_beats, Some(7_f64));
+        assert_eq!(transport.bar_start_pos_beats, Some(6_f64));
+        assert_eq!(transport.bar_number, Some(2));
+
+        transport.advance(-24000 * 2);
+        assert_eq!(transport.pos_beats, Some(5_f64));
+        assert_eq!(transport.bar_start_pos_beats, Some(3_f64));
+        assert_eq!(transport.bar_number, Some(1));
+
+        transport.tempo = None;
+        transport.advance(1);
+        assert_eq!(transport.pos_samples, Some(24000 * 5 + 1));
+        assert_eq!(transport.pos_beats, None);
+        assert_eq!(transport.bar_number, None);
+
+        let mut stopped = TransportInfo {
+            playing: false,
+            ..playing()
+        };
+        stopped.advance(24000);
+        assert_eq!(
+            stopped,
+            TransportInfo {
+                playing: false,
+                ..playing()
+            }
+        );
+    }
+}
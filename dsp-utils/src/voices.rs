@@ -0,0 +1,801 @@
+use std::sync::Arc;
+
+use nih_plug::prelude::NoteEvent;
+use nih_plug::prelude::Plugin;
+use nih_plug::prelude::ProcessContext;
+use nih_plug::prelude::ProcessStatus;
+use nih_plug::prelude::SysExMessage;
+
+use crate::BlockContext;
+use crate::MultiChannelProcessor;
+use crate::ParamsBlock;
+use crate::Sample;
+
+/// Per note expression, see [`VoiceEvent::Expression`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Expression {
+    /// Polyphonic aftertouch, `0.0..=1.0`
+    Pressure,
+    /// Gain, `0.0..=4.0` with `1.0` being unchanged
+    Volume,
+    /// Pan, `-1.0..=1.0`
+    Pan,
+    /// Tuning, in semitones
+    Tuning,
+    /// Vibrato amount, `0.0..=1.0`
+    Vibrato,
+    /// Generic expression, `0.0..=1.0`
+    Expression,
+    /// Brightness, `0.0..=1.0`
+    Brightness,
+}
+
+/// Event for a single voice, with timing relative to the start of the block being processed
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum VoiceEvent {
+    /// Start playing `note`. Can arrive while the voice still plays a stolen note, fade that one out quickly
+    NoteOn {
+        timing: usize,
+        note: u8,
+        velocity: f32,
+    },
+    /// Release the note, the voice keeps playing until [`VoiceProcessor::is_active`] returns `false`
+    NoteOff { timing: usize, velocity: f32 },
+    /// Stop the note right away, the voice is considered idle after the block
+    Choke { timing: usize },
+    /// Offset the param with `poly_modulation_id` by `normalized_offset`, for this voice only
+    PolyModulation {
+        timing: usize,
+        poly_modulation_id: u32,
+        normalized_offset: f32,
+    },
+    /// New value of the param with `poly_modulation_id`, to apply the poly modulation offset to
+    MonoAutomation {
+        timing: usize,
+        poly_modulation_id: u32,
+        normalized_value: f32,
+    },
+    /// Per note expression
+    Expression {
+        timing: usize,
+        expression: Expression,
+        value: f32,
+    },
+}
+
+impl VoiceEvent {
+    /// Index of the sample in the block the event belongs to
+    pub fn timing(&self) -> usize {
+        match *self {
+            VoiceEvent::NoteOn { timing, .. }
+            | VoiceEvent::NoteOff { timing, .. }
+            | VoiceEvent::Choke { timing }
+            | VoiceEvent::PolyModulation { timing, .. }
+            | VoiceEvent::MonoAutomation { timing, .. }
+            | VoiceEvent::Expression { timing, .. } => timing,
+        }
+    }
+}
+
+/// How a [`VoiceManager`] handles a note when all voices are playing
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum VoiceStealing {
+    /// Restart the voice playing the oldest note, preferring released ones
+    #[default]
+    Oldest,
+    /// Ignore the note
+    None,
+}
+
+/// A single voice of a synth, driven by a [`VoiceManager`]
+///
+/// Like [`crate::SingleChannelProcessor`], every voice gets blocks of exactly `block_size` samples. The events of a block are
+/// passed together with it, use their timing to start and stop notes on the right sample.
+pub trait VoiceProcessor<T: Sample = f32>: Send {
+    /// Type that stores blocks of params, shared by all voices
+    type ParamsBlock: ParamsBlock;
+
+    /// Number of voices, all created up front
+    const VOICES: usize = 16;
+    /// What happens to notes when all voices are playing
+    const STEALING: VoiceStealing = VoiceStealing::Oldest;
+
+    /// Initialize the voice. Allocate here, voices are not created while processing
+    fn new(
+        block_size: usize,
+        channels: usize,
+        sample_rate: f32,
+        params: Arc<<Self::ParamsBlock as ParamsBlock>::Params>,
+    ) -> Self;
+
+    /// Add the output of the voice onto `outputs`, one block per channel.
+    ///
+    /// `events` holds the events for this voice in the block, in the order they were received. Only called for voices that
+    /// are active or receive a note.
+    fn process(
+        &mut self,
+        outputs: &mut [Vec<T>],
+        params_block: &Self::ParamsBlock,
+        events: &[VoiceEvent],
+    ) -> ProcessStatus;
+
+    /// Whether the voice still plays, including its release tail. The voice becomes idle once this returns `false`
+    fn is_active(&self) -> bool;
+
+    /// Clear all state, e.g. filters and envelopes. Called when the voice manager is reset
+    fn reset(&mut self) {}
+}
+
+// Voice that ended, see `VoiceManager::send_terminated_voices`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct Terminated {
+    voice_id: Option<i32>,
+    channel: u8,
+    note: u8,
+}
+
+// A voice with the note it plays
+struct Slot<V> {
+    voice: V,
+    active: bool,
+    released: bool,
+    // Order of the note on, to find the oldest voice
+    age: u64,
+    voice_id: Option<i32>,
+    channel: u8,
+    note: u8,
+    events: Vec<VoiceEvent>,
+}
+
+impl<V> Slot<V> {
+    fn plays(&self, voice_id: Option<i32>, channel: u8, note: u8) -> bool {
+        self.active
+            && match voice_id {
+                Some(voice_id) => self.voice_id == Some(voice_id),
+                None => self.channel == channel && self.note == note,
+            }
+    }
+
+    fn terminated(&self) -> Terminated {
+        Terminated {
+            voice_id: self.voice_id,
+            channel: self.channel,
+            note: self.note,
+        }
+    }
+
+    // Whether another event fits in the queue without allocating
+    fn has_room(&self) -> bool {
+        self.events.len() < self.events.capacity()
+    }
+
+    // Queues an event for the voice, dropping it once the capacity is reached so it never allocates
+    fn push(&mut self, event: VoiceEvent) {
+        if self.has_room() {
+            self.events.push(event);
+        }
+    }
+}
+
+/// Polyphonic synth, playing every note with a [`VoiceProcessor`] from a fixed pool
+///
+/// Drive it with a [`crate::LinkedDspCoreProcessor`] and [`crate::LinkedDspCoreProcessor::process_with_context`], which
+/// passes it the note events. It allocates voices on note on, releases them on note off, steals voices according to
+/// [`VoiceProcessor::STEALING`], routes polyphonic modulation and expression to the voice of the note, and sums all voices into
+/// the output. The input is ignored. Voices that ended are reported with [`Self::send_terminated_voices`].
+///
+/// ```no_run
+/// type Synth = LinkedDspCoreProcessor<VoiceManager<SynthVoice>>;
+///
+/// let mut synth = Synth::new(params, 64, 2, sample_rate);
+/// // In `Plugin::process`
+/// synth.process_with_context(buffer, aux, context);
+/// synth.processor_mut().send_terminated_voices(context);
+/// ```
+pub struct VoiceManager<V> {
+    slots: Vec<Slot<V>>,
+    age: u64,
+    terminated: Vec<Terminated>,
+    dropped_terminations: usize,
+}
+
+impl<V> VoiceManager<V> {
+    /// Number of voices playing, including released ones
+    pub fn active_voices(&self) -> usize {
+        self.slots.iter().filter(|slot| slot.active).count()
+    }
+
+    /// Number of ended voices that were not reported, because more voices ended between two calls to
+    /// [`Self::send_terminated_voices`] than fit in the queue, `VOICES * 4`
+    pub fn dropped_terminations(&self) -> usize {
+        self.dropped_terminations
+    }
+
+    /// Send a [`NoteEvent::VoiceTerminated`] for every voice that ended since the last call.
+    ///
+    /// Call it after processing, so the host can free the polyphonic modulation of the voices. The events are sent at the
+    /// start of the buffer.
+    pub fn send_terminated_voices<P: Plugin>(&mut self, context: &mut impl ProcessContext<P>) {
+        for terminated in self.terminated.drain(..) {
+            context.send_event(NoteEvent::VoiceTerminated {
+                timing: 0,
+                voice_id: terminated.voice_id,
+                channel: terminated.channel,
+                note: terminated.note,
+            });
+        }
+    }
+
+    // Records a terminated voice, dropping and counting it once the capacity is reached so it never allocates
+    fn terminate(&mut self, terminated: Terminated) {
+        if self.terminated.len() < self.terminated.capacity() {
+            self.terminated.push(terminated);
+        } else {
+            self.dropped_terminations += 1;
+        }
+    }
+
+    // Index of the voice to play a new note with, if any
+    fn allocate(&self, stealing: VoiceStealing) -> Option<usize> {
+        if let Some(index) = self.slots.iter().position(|slot| !slot.active) {
+            return Some(index);
+        }
+        match stealing {
+            VoiceStealing::Oldest => self
+                .slots
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, slot)| (!slot.released, slot.age))
+                .map(|(index, _)| index),
+            VoiceStealing::None => None,
+        }
+    }
+
+    // Sends `event` to the voices playing the note
+    fn route(&mut self, voice_id: Option<i32>, channel: u8, note: u8, event: VoiceEvent) {
+        for slot in self
+            .slots
+            .iter_mut()
+            .filter(|slot| slot.plays(voice_id, channel, note))
+        {
+            slot.push(event);
+        }
+    }
+
+    fn note_on<S, T: Sample>(
+        &mut self,
+        event: &NoteEvent<S>,
+        timing: usize,
+        stealing: VoiceStealing,
+    ) where
+        V: VoiceProcessor<T>,
+    {
+        let NoteEvent::NoteOn {
+            voice_id,
+            channel,
+            note,
+            velocity,
+            ..
+        } = *event
+        else {
+            return;
+        };
+        let Some(index) = self.allocate(stealing) else {
+            return;
+        };
+        // A voice without its note on would play a note it never started
+        if !self.slots[index].has_room() {
+            return;
+        }
+
+        if self.slots[index].active {
+            let terminated = self.slots[index].terminated();
+            self.terminate(terminated);
+        }
+        self.age += 1;
+        let slot = &mut self.slots[index];
+        slot.active = true;
+        slot.released = false;
+        slot.age = self.age;
+        slot.voice_id = voice_id;
+        slot.channel = channel;
+        slot.note = note;
+        slot.push(VoiceEvent::NoteOn {
+            timing,
+            note,
+            velocity,
+        });
+    }
+
+    fn handle_event<S, T: Sample>(&mut self, event: &NoteEvent<S>, timing: usize)
+    where
+        V: VoiceProcessor<T>,
+    {
+        match *event {
+            NoteEvent::NoteOn { .. } => self.note_on::<S, T>(event, timing, V::STEALING),
+            NoteEvent::NoteOff {
+                voice_id,
+                channel,
+                note,
+                velocity,
+                ..
+            } => {
+                for slot in self
+                    .slots
+                    .iter_mut()
+                    .filter(|slot| !slot.released && slot.plays(voice_id, channel, note))
+                {
+                    slot.released = true;
+                    slot.push(VoiceEvent::NoteOff { timing, velocity });
+                }
+            }
+            NoteEvent::Choke {
+                voice_id,
+                channel,
+                note,
+                ..
+            } => self.route(voice_id, channel, note, VoiceEvent::Choke { timing }),
+            NoteEvent::PolyModulation {
+                voice_id,
+                poly_modulation_id,
+                normalized_offset,
+                ..
+            } => {
+                for slot in self
+                    .slots
+                    .iter_mut()
+                    .filter(|slot| slot.active && slot.voice_id == Some(voice_id))
+                {
+                    slot.push(VoiceEvent::PolyModulation {
+                        timing,
+                        poly_modulation_id,
+                        normalized_offset,
+                    });
+                }
+            }
+            NoteEvent::MonoAutomation {
+                poly_modulation_id,
+                normalized_value,
+                ..
+            } => {
+                for slot in self.slots.iter_mut().filter(|slot| slot.active) {
+                    slot.push(VoiceEvent::MonoAutomation {
+                        timing,
+                        poly_modulation_id,
+                        normalized_value,
+                    });
+                }
+            }
+            _ => {
+                if let Some((voice_id, channel, note, expression, value)) = expression(event) {
+                    self.route(
+                        voice_id,
+                        channel,
+                        note,
+                        VoiceEvent::Expression {
+                            timing,
+                            expression,
+                            value,
+                        },
+                    );
+                }
+            }
+        }
+    }
+}
+
+// Target and value of a per note expression event
+fn expression<S>(event: &NoteEvent<S>) -> Option<(Option<i32>, u8, u8, Expression, f32)> {
+    Some(match *event {
+        NoteEvent::PolyPressure {
+            voice_id,
+            channel,
+            note,
+            pressure,
+            ..
+        } => (voice_id, channel, note, Expression::Pressure, pressure),
+        NoteEvent::PolyVolume {
+            voice_id,
+            channel,
+            note,
+            gain,
+            ..
+        } => (voice_id, channel, note, Expression::Volume, gain),
+        NoteEvent::PolyPan {
+            voice_id,
+            channel,
+            note,
+            pan,
+            ..
+        } => (voice_id, channel, note, Expression::Pan, pan),
+        NoteEvent::PolyTuning {
+            voice_id,
+            channel,
+            note,
+            tuning,
+            ..
+        } => (voice_id, channel, note, Expression::Tuning, tuning),
+        NoteEvent::PolyVibrato {
+            voice_id,
+            channel,
+            note,
+            vibrato,
+            ..
+        } => (voice_id, channel, note, Expression::Vibrato, vibrato),
+        NoteEvent::PolyExpression {
+            voice_id,
+            channel,
+            note,
+            expression,
+            ..
+        } => (voice_id, channel, note, Expression::Expression, expression),
+        NoteEvent::PolyBrightness {
+            voice_id,
+            channel,
+            note,
+            brightness,
+            ..
+        } => (voice_id, channel, note, Expression::Brightness, brightness),
+        _ => return None,
+    })
+}
+
+impl<T: Sample, V: VoiceProcessor<T>> MultiChannelProcessor<T> for VoiceManager<V> {
+    type ParamsBlock = V::ParamsBlock;
+
+    fn new(
+        block_size: usize,
+        channels: usize,
+        sample_rate: f32,
+        params: Arc<<Self::ParamsBlock as ParamsBlock>::Params>,
+    ) -> Self {
+        Self {
+            slots: (0..V::VOICES)
+                .map(|_| Slot {
+                    voice: V::new(block_size, channels, sample_rate, params.clone()),
+                    active: false,
+                    released: false,
+                    age: 0,
+                    voice_id: None,
+                    channel: 0,
+                    note: 0,
+                    events: Vec::with_capacity(crate::EVENT_CAPACITY),
+                })
+                .collect(),
+            age: 0,
+            terminated: Vec::with_capacity(V::VOICES * 4),
+            dropped_terminations: 0,
+        }
+    }
+
+    fn process(
+        &mut self,
+        blocks: &[Vec<T>],
+        outputs: &mut [Vec<T>],
+        params_block: &Self::ParamsBlock,
+    ) -> ProcessStatus {
+        self.process_with_context(
+            blocks,
+            outputs,
+            params_block,
+            &BlockContext::<T, ()>::default(),
+        )
+    }
+
+    fn process_with_context<S: SysExMessage>(
+        &mut self,
+        _blocks: &[Vec<T>],
+        outputs: &mut [Vec<T>],
+        params_block: &Self::ParamsBlock,
+        context: &BlockContext<T, S>,
+    ) -> ProcessStatus {
+        for block in outputs.iter_mut() {
+            block.fill(T::ZERO);
+        }
+        for slot in self.slots.iter_mut() {
+            slot.events.clear();
+        }
+        for event in context.events() {
+            self.handle_event::<S, T>(&event.event, event.timing);
+        }
+
+        let mut status = ProcessStatus::Normal;
+        for index in 0..self.slots.len() {
+            let slot = &mut self.slots[index];
+            if !slot.active {
+                continue;
+            }
+            if let ProcessStatus::Error(e) = slot.voice.process(outputs, params_block, &slot.events)
+            {
+                status = ProcessStatus::Error(e);
+            }
+
+            let choked = slot
+                .events
+                .iter()
+                .any(|event| matches!(event, VoiceEvent::Choke { .. }));
+            if choked || !slot.voice.is_active() {
+                slot.active = false;
+                let terminated = slot.terminated();
+                self.terminate(terminated);
+            }
+        }
+        status
+    }
+
+    fn tail(&self) -> usize {
+        // Playing voices decide themselves when their release ends, idle voices are only started by note events, which
+        // wake the plugin up anyway
+        if self.slots.iter().any(|slot| slot.active) {
+            usize::MAX
+        } else {
+            0
+        }
+    }
+
+    fn reset(&mut self) {
+        for index in 0..self.slots.len() {
+            if self.slots[index].active {
+                let terminated = self.slots[index].terminated();
+                self.terminate(terminated);
+            }
+            let slot = &mut self.slots[index];
+            slot.active = false;
+            slot.voice.reset();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use nih_plug::params::Params;
+    use nih_plug::prelude::{NoteEvent, ProcessStatus};
+
+    use super::{Terminated, VoiceEvent, VoiceManager, VoiceProcessor};
+    use crate::{BlockContext, BlockEvent, MultiChannelProcessor, ParamsBlock, TransportInfo};
+
+    #[derive(Params)]
+    struct ImplementsParams {}
+    struct Block {}
+    impl ParamsBlock for Block {
+        type Params = ImplementsParams;
+        fn new(_params: Arc<Self::Params>, _block_size: usize) -> Self {
+            Self {}
+        }
+        fn from_params(&mut self) {}
+    }
+
+    // Outputs its note from the note on, and rings for one block after the note off
+    struct Held {
+        note: Option<u8>,
+        release: usize,
+    }
+    impl VoiceProcessor for Held {
+        type ParamsBlock = Block;
+        const VOICES: usize = 2;
+
+        fn new(
+            _block_size: usize,
+            _channels: usize,
+            _sample_rate: f32,
+            _params: Arc<ImplementsParams>,
+        ) -> Self {
+            Self {
+                note: None,
+                release: 0,
+            }
+        }
+        fn process(
+            &mut self,
+            outputs: &mut [Vec<f32>],
+            _params_block: &Self::ParamsBlock,
+            events: &[VoiceEvent],
+        ) -> ProcessStatus {
+            let mut events = events.iter().peekable();
+            for i in 0..outputs[0].len() {
+                while let Some(event) = events.next_if(|event| event.timing() == i) {
+                    match *event {
+                        VoiceEvent::NoteOn { note, .. } => {
+                            self.note = Some(note);
+                            self.release = 0;
+                        }
+                        VoiceEvent::NoteOff { .. } => self.release = 2,
+                        _ => {}
+                    }
+                }
+                for output in outputs.iter_mut() {
+                    output[i] += self.note.map_or(0_f32, |note| note as f32);
+                }
+            }
+            if self.release > 0 {
+                self.release -= 1;
+                if self.release == 0 {
+                    self.note = None;
+                }
+            }
+            ProcessStatus::Normal
+        }
+        fn is_active(&self) -> bool {
+            self.note.is_some()
+        }
+    }
+
+    fn note_on(timing: usize, note: u8) -> BlockEvent<()> {
+        BlockEvent {
+            timing,
+            event: NoteEvent::NoteOn {
+                timing: 0,
+                voice_id: None,
+                channel: 0,
+                note,
+                velocity: 1_f32,
+            },
+        }
+    }
+
+    fn note_off(timing: usize, note: u8) -> BlockEvent<()> {
+        BlockEvent {
+            timing,
+            event: NoteEvent::NoteOff {
+                timing: 0,
+                voice_id: None,
+                channel: 0,
+                note,
+                velocity: 1_f32,
+            },
+        }
+    }
+
+    fn process(manager: &mut VoiceManager<Held>, events: &[BlockEvent<()>]) -> Vec<f32> {
+        let mut outputs = vec![vec![0_f32; 4]];
+        let params_block = Block {};
+        manager.process_with_context(
+            &[vec![0_f32; 4]],
+            &mut outputs,
+            &params_block,
+            &BlockContext::new(&[], events, TransportInfo::default()),
+        );
+        outputs.remove(0)
+    }
+
+    #[test]
+    fn test_voices() {
+        let mut manager: VoiceManager<Held> =
+            VoiceManager::new(4, 1, 48000_f32, Arc::new(ImplementsParams {}));
+
+        // Starts on the sample of the note on, and sums the voices
+        assert_eq!(
+            vec![0_f32, 1_f32, 3_f32, 3_f32],
+            process(&mut manager, &[note_on(1, 1), note_on(2, 2)])
+        );
+        assert_eq!(2, manager.active_voices());
+        assert_eq!(usize::MAX, manager.tail());
+
+        // Released voices keep playing their tail, and are stolen first
+        assert_eq!(vec![3_f32; 4], process(&mut manager, &[note_off(0, 1)]));
+        assert_eq!(
+            vec![3_f32, 6_f32, 6_f32, 6_f32],
+            process(&mut manager, &[note_on(1, 4)])
+        );
+        assert_eq!(
+            vec![Terminated {
+                voice_id: None,
+                channel: 0,
+                note: 1
+            }],
+            manager.terminated
+        );
+
+        // All voices are held, the oldest one is stolen
+        assert_eq!(vec![11_f32; 4], process(&mut manager, &[note_on(0, 7)]));
+        assert_eq!(2, manager.terminated.len());
+
+        // Voices become idle after their release
+        process(&mut manager, &[note_off(0, 4), note_off(0, 7)]);
+        assert_eq!(vec![11_f32; 4], process(&mut manager, &[]));
+        assert_eq!(vec![0_f32; 4], process(&mut manager, &[]));
+        assert_eq!(0, manager.active_voices());
+        assert_eq!(4, manager.terminated.len());
+        assert_eq!(0, manager.tail());
+    }
+
+    #[test]
+    fn test_poly_modulation() {
+        let mut manager: VoiceManager<Held> =
+            VoiceManager::new(4, 1, 48000_f32, Arc::new(ImplementsParams {}));
+        let on = |voice_id, note| BlockEvent {
+            timing: 0,
+            event: NoteEvent::NoteOn {
+                timing: 0,
+                voice_id: Some(voice_id),
+                channel: 0,
+                note,
+                velocity: 1_f32,
+            },
+        };
+        process(&mut manager, &[on(10, 1), on(11, 2)]);
+
+        // Only reaches the voice with the id, automation reaches all of them
+        let events = [
+            BlockEvent {
+                timing: 2,
+                event: NoteEvent::PolyModulation {
+                    timing: 0,
+                    voice_id: 11,
+                    poly_modulation_id: 3,
+                    normalized_offset: 0.5_f32,
+                },
+            },
+            BlockEvent {
+                timing: 3,
+                event: NoteEvent::MonoAutomation {
+                    timing: 0,
+                    poly_modulation_id: 3,
+                    normalized_value: 0.25_f32,
+                },
+            },
+        ];
+        process(&mut manager, &events);
+
+        let automation = VoiceEvent::MonoAutomation {
+            timing: 3,
+            poly_modulation_id: 3,
+            normalized_value: 0.25_f32,
+        };
+        assert_eq!(vec![automation], manager.slots[0].events);
+        assert_eq!(
+            vec![
+                VoiceEvent::PolyModulation {
+                    timing: 2,
+                    poly_modulation_id: 3,
+                    normalized_offset: 0.5_f32,
+                },
+                automation
+            ],
+            manager.slots[1].events
+        );
+    }
+
+    // Makes sure the event queues of the voices never grow past their capacity
+
+    #[test]
+    fn test_event_capacity() {
+        let mut manager: VoiceManager<Held> =
+            VoiceManager::new(4, 1, 48000_f32, Arc::new(ImplementsParams {}));
+        let automation = BlockEvent {
+            timing: 0,
+            event: NoteEvent::MonoAutomation {
+                timing: 0,
+                poly_modulation_id: 3,
+                normalized_value: 0.25_f32,
+            },
+        };
+        let mut events = vec![note_on(0, 1)];
+        events.resize(crate::EVENT_CAPACITY * 2, automation);
+        process(&mut manager, &events);
+
+        let slot = &manager.slots[0];
+        assert_eq!(crate::EVENT_CAPACITY, slot.events.capacity());
+        assert_eq!(crate::EVENT_CAPACITY, slot.events.len());
+        assert!(matches!(slot.events[0], VoiceEvent::NoteOn { note: 1, .. }));
+    }
+
+    // Makes sure terminated voices that do not fit in the queue are counted
+
+    #[test]
+    fn test_dropped_terminations() {
+        let mut manager: VoiceManager<Held> =
+            VoiceManager::new(4, 1, 48000_f32, Arc::new(ImplementsParams {}));
+        let capacity = manager.terminated.capacity();
+
+        // Every note after the first two steals a voice
+        let events: Vec<BlockEvent<()>> = (0..capacity + 4)
+            .map(|note| note_on(0, note as u8))
+            .collect();
+        process(&mut manager, &events);
+        assert_eq!(capacity, manager.terminated.len());
+        assert_eq!(2, manager.dropped_terminations());
+    }
+}
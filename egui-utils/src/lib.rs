@@ -6,7 +6,10 @@ mod widgets;
 pub use widgets::ArcKnob;
 pub use widgets::CheckboxLayout;
 pub use widgets::KnobLayout;
+pub use widgets::LevelMeter;
 pub use widgets::ParamCheckbox;
+pub use widgets::Scope;
+pub use widgets::ScopeData;
 
 pub use widgets::ferra_color;
 pub use widgets::WidgetStyle;
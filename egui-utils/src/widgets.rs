@@ -6,6 +6,11 @@ mod checkbox;
 pub use checkbox::CheckboxLayout;
 pub use checkbox::ParamCheckbox;
 
+mod meter;
+pub use meter::LevelMeter;
+pub use meter::Scope;
+pub use meter::ScopeData;
+
 mod style;
 pub use style::ferra_color;
 pub use style::WidgetStyle;
@@ -0,0 +1,203 @@
+use dsp_utils::AtomicF32;
+use dsp_utils::Consumer;
+
+use nih_plug::util;
+
+use nih_plug_egui::egui;
+use nih_plug_egui::egui::CornerRadius;
+use nih_plug_egui::egui::Pos2;
+use nih_plug_egui::egui::Rect;
+use nih_plug_egui::egui::Response;
+use nih_plug_egui::egui::Sense;
+use nih_plug_egui::egui::Shape;
+use nih_plug_egui::egui::Stroke;
+use nih_plug_egui::egui::Ui;
+use nih_plug_egui::egui::Vec2;
+use nih_plug_egui::egui::Widget;
+
+use super::WidgetStyle;
+
+/// Vertical meter for a gain value shared by the processor, e.g. a peak level or gain reduction
+///
+/// Reads the [`AtomicF32`] once per frame and shows it in dB, without locking.
+pub struct LevelMeter<'a> {
+    level: &'a AtomicF32,
+    style: &'a WidgetStyle,
+    min_db: f32,
+    max_db: f32,
+    hover_text: String,
+}
+
+impl<'a> LevelMeter<'a> {
+    pub fn new(level: &'a AtomicF32, style: &'a WidgetStyle) -> Self {
+        Self {
+            level,
+            style,
+            min_db: -60_f32,
+            max_db: 6_f32,
+            hover_text: String::new(),
+        }
+    }
+
+    /// Set the dB values at the bottom and top of the meter, `-60_f32` and `6_f32` by default
+    pub fn set_range(mut self, min_db: f32, max_db: f32) -> Self {
+        self.min_db = min_db;
+        self.max_db = max_db;
+        self
+    }
+
+    pub fn set_hover_text(mut self, t: String) -> Self {
+        self.hover_text = t;
+        self
+    }
+}
+
+impl<'a> Widget for LevelMeter<'a> {
+    fn ui(self, ui: &mut Ui) -> Response {
+        let size = Vec2::new(self.style.element_size * 0.25_f32, self.style.element_size);
+        let resp = ui.allocate_response(size, Sense::hover());
+        let rect = resp.rect.shrink(self.style.padding);
+
+        draw_background(ui, rect, self.style);
+
+        let db = util::gain_to_db(self.level.load());
+        let fill = ((db - self.min_db) / (self.max_db - self.min_db)).clamp(0_f32, 1_f32);
+        let level_rect = Rect::from_min_max(
+            Pos2::new(rect.min.x, rect.max.y - rect.height() * fill),
+            rect.max,
+        );
+        ui.painter().rect_filled(
+            level_rect,
+            CornerRadius::same(self.style.background_radius),
+            self.style.element_accent_color,
+        );
+
+        // The level changes without any input, keep redrawing
+        ui.ctx().request_repaint();
+
+        if self.style.show_hover_text {
+            resp.on_hover_text_at_pointer(if self.hover_text.is_empty() {
+                format!("{:.1} dB", db)
+            } else {
+                self.hover_text
+            })
+        } else {
+            resp
+        }
+    }
+}
+
+/// Samples received from the processor for a [`Scope`], kept between frames
+///
+/// Create it once with the [`Consumer`] end of a [`dsp_utils::ring_buffer`], and store it in your editor state.
+pub struct ScopeData {
+    consumer: Consumer<f32>,
+    samples: Vec<f32>,
+    position: usize,
+}
+
+impl ScopeData {
+    /// Show the last `length` samples pushed to the ring buffer
+    pub fn new(consumer: Consumer<f32>, length: usize) -> Self {
+        assert!(length > 0, "Scope length cannot be zero");
+        Self {
+            consumer,
+            samples: vec![0_f32; length],
+            position: 0,
+        }
+    }
+
+    /// Take the samples pushed since the last call, without allocating. Called by [`Scope`] every frame
+    pub fn update(&mut self) {
+        while let Some(sample) = self.consumer.pop() {
+            self.samples[self.position] = sample;
+            self.position = (self.position + 1) % self.samples.len();
+        }
+    }
+
+    /// Iterate the samples from the oldest to the newest
+    pub fn samples(&self) -> impl Iterator<Item = f32> + '_ {
+        self.samples[self.position..]
+            .iter()
+            .chain(&self.samples[..self.position])
+            .copied()
+    }
+}
+
+/// Oscilloscope drawing the latest samples of a [`ScopeData`]
+pub struct Scope<'a> {
+    data: &'a mut ScopeData,
+    style: &'a WidgetStyle,
+    range: f32,
+}
+
+impl<'a> Scope<'a> {
+    pub fn new(data: &'a mut ScopeData, style: &'a WidgetStyle) -> Self {
+        Self {
+            data,
+            style,
+            range: 1_f32,
+        }
+    }
+
+    /// Set the sample value at the top of the scope, `1_f32` by default
+    pub fn set_range(mut self, range: f32) -> Self {
+        self.range = range;
+        self
+    }
+}
+
+impl<'a> Widget for Scope<'a> {
+    fn ui(mut self, ui: &mut Ui) -> Response {
+        self.data.update();
+
+        let size = Vec2::new(self.style.element_size * 2_f32, self.style.element_size);
+        let resp = ui.allocate_response(size, Sense::hover());
+        let rect = resp.rect.shrink(self.style.padding);
+
+        draw_background(ui, rect, self.style);
+
+        let step = rect.width() / (self.data.samples.len().max(2) - 1) as f32;
+        let points: Vec<Pos2> = self
+            .data
+            .samples()
+            .enumerate()
+            .map(|(i, sample)| {
+                let y = (sample / self.range).clamp(-1_f32, 1_f32);
+                Pos2::new(
+                    rect.min.x + i as f32 * step,
+                    rect.center().y - y * rect.height() * 0.5_f32,
+                )
+            })
+            .collect();
+        ui.painter_at(rect).add(Shape::line(
+            points,
+            Stroke::new(self.style.line_width * 0.5_f32, self.style.line_color),
+        ));
+
+        // The samples change without any input, keep redrawing
+        ui.ctx().request_repaint();
+
+        resp
+    }
+}
+
+// Draws the background and outline shared by the meters
+fn draw_background(ui: &mut Ui, rect: Rect, style: &WidgetStyle) {
+    ui.painter().rect_filled(
+        rect,
+        CornerRadius::same(style.background_radius),
+        style
+            .background_color
+            .gamma_multiply(style.background_opacity),
+    );
+
+    if style.outline {
+        ui.painter().rect_stroke(
+            rect,
+            CornerRadius::same(style.background_radius),
+            Stroke::new(2_f32, style.line_color),
+            egui::StrokeKind::Middle,
+        );
+    }
+}